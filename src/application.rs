@@ -1,38 +1,141 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
-use crossterm::event::{self, KeyCode, KeyModifiers};
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
 use ratatui::{
     DefaultTerminal,
-    layout::{Constraint, Layout},
+    layout::{Constraint, Layout, Margin, Position, Rect},
+    style::{Color, Stylize},
+    text::{Line, ToSpan},
+    widgets::Block,
+};
+
+use crate::{
+    config,
+    events::{self, Events},
+    import,
+    logger::Logger,
+    snapshot, source, tmux, ui,
 };
 
-use crate::{config, logger::Logger, tmux, ui};
+/// Second click on the same session within this time opens it
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
 
 pub struct Application {
-    config: Vec<config::Session>,
     terminal: DefaultTerminal,
+    events: Events,
     logger: Logger,
     list: ui::SessionList,
     prompt: ui::Prompt,
-    selected: Option<(ui::MatchedString, ui::Session)>,
+    log_view: ui::LogView,
+    help_view: ui::HelpView,
+    selected: Option<(ui::MatchedString, ui::Session, tmux::Mode)>,
+    /// External terminal command, see [config::Tmux::terminal]
+    terminal_command: String,
+    servers: Vec<config::Server>,
+    refresh_interval: Duration,
+    next_refresh: Instant,
+    snapshot: config::Snapshot,
+    next_snapshot: Option<Instant>,
+    /// Whether snapshot is being saved in background
+    saving: Arc<AtomicBool>,
+    config_path: std::path::PathBuf,
+    /// Sources with time of next read and sessions listed by them
+    /// Time is none while source is being read in background
+    sources: Vec<(config::Source, Option<Instant>, HashSet<ui::SessionKey>)>,
+    injector: ui::SessionInjector,
+    discovered: Arc<AtomicBool>,
+    rendered_messages: usize,
+    dirty: bool,
     help: bool,
+    logs: bool,
+    ui: config::Ui,
+    theme: ui::Theme,
+    /// Config path and default server shown in header
+    header: String,
+    /// Screen and list areas of last render, clicks are mapped through them
+    screen: Rect,
+    list_area: Rect,
+    /// Time and session of last click on list
+    last_click: Option<(Instant, ui::SessionKey)>,
     running: bool,
 }
 
 impl Application {
     pub fn new(config: config::Config) -> std::io::Result<Self> {
+        let events = Events::new();
+        let list = ui::SessionList::new(
+            &config.search.fields,
+            events.notifier(events::Event::Matcher),
+        );
+        let injector = list.injector();
+        let now = Instant::now();
         let mut app = Self {
-            config: config.session,
             terminal: ratatui::init(),
-            logger: Logger::new(&config.logger)?,
-            list: ui::SessionList::new(),
+            logger: Logger::new(&config.logger, events.notifier(events::Event::Message))?,
+            list,
+            events,
             prompt: ui::Prompt::new(),
+            log_view: ui::LogView::new(),
+            help_view: ui::HelpView::new(),
             selected: None,
+            terminal_command: config.tmux.terminal.clone(),
+            servers: config.servers(),
+            refresh_interval: config.tmux.refresh_interval.to_std().unwrap_or_default(),
+            next_refresh: Instant::now(),
+            next_snapshot: config.snapshot.interval.map(|_| Instant::now()),
+            snapshot: config.snapshot,
+            saving: Arc::new(AtomicBool::new(false)),
+            config_path: config.path.clone(),
+            sources: config
+                .source
+                .into_iter()
+                .map(|s| (s, Some(now), HashSet::new()))
+                .collect(),
+            injector: injector.clone(),
+            discovered: Arc::new(AtomicBool::new(false)),
+            rendered_messages: 0,
+            dirty: true,
             help: false,
+            logs: false,
+            theme: ui::Theme::new(&config.ui),
+            header: format!("{}  server: {}", config.path.display(), config.tmux.server),
+            screen: Rect::default(),
+            list_area: Rect::default(),
+            last_click: None,
             running: true,
+            ui: config.ui,
         };
+        app.list
+            .set_rev(app.ui.orientation == config::Orientation::BottomUp);
+        app.capture_mouse(true);
+        if config.tmux.control_mode {
+            app.connect();
+        }
         app.refresh();
 
+        // globs may match lots of directories, so sessions are discovered in background
+        // sources are read on refresh after that, so that configured sessions take precedence
+        let (import, server) = (config.import, config.tmux.server);
+        let (discovered, notify) = (
+            app.discovered.clone(),
+            app.events.notifier(events::Event::Refresh),
+        );
+        std::thread::spawn(move || {
+            discover(config.session, &injector);
+            discover(import_sessions(&import, &server), &injector);
+            log::trace!("Session discovery finished");
+            discovered.store(true, Ordering::Release);
+            notify();
+        });
+
         if config.show_help {
             log::info!("ctrl+?/ctrl+7 show help");
         }
@@ -44,29 +147,109 @@ impl Application {
         self.running
     }
 
+    /// Redraws screen, if anything has changed since last render
     pub fn render(&mut self) {
+        if !self.dirty {
+            return;
+        }
         self.terminal
             .draw(|frame| {
                 let area = frame.area();
 
+                // display frame & header
+                let inner = match self.ui.border {
+                    true => {
+                        let block = Block::bordered()
+                            .border_set(self.theme.border)
+                            .border_style(Color::DarkGray);
+                        let inner = block.inner(area);
+                        frame.render_widget(block, area);
+                        inner
+                    }
+                    false => area,
+                };
+                let layout = Layout::vertical([
+                    Constraint::Length(self.ui.header as u16),
+                    Constraint::Fill(1),
+                ]);
+                let [header_area, inner] = layout.areas(inner);
+                frame.render_widget(
+                    Line::from(vec![
+                        "tiramisu ".to_span().blue().bold(),
+                        self.header.as_str().dark_gray(),
+                    ]),
+                    header_area,
+                );
+
                 // display fzf & prompt
-                let layout = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]);
-                let [list_area, prompt_area] = layout.areas(area);
+                let [list_area, prompt_area] = match self.ui.prompt {
+                    config::PromptPosition::Top => {
+                        let layout = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
+                        let [prompt_area, list_area] = layout.areas(inner);
+                        [list_area, prompt_area]
+                    }
+                    config::PromptPosition::Bottom => {
+                        Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner)
+                    }
+                };
+                let list_area = list_area.inner(Margin::new(self.ui.padding, 0));
+                (self.screen, self.list_area) = (area, list_area);
 
-                let hint = format!("{}/{}", self.list.matched_items().len(), self.list.len());
-                frame.render_widget(ui::PromtWidget::new(&self.prompt, &hint), prompt_area);
-                frame.set_cursor_position((
-                    area.x + self.prompt.cursor() as u16,
-                    area.y + area.height,
-                ));
-                frame.render_widget(ui::SessionListWidget::new(&self.list), list_area);
+                if self.logs {
+                    // log view replaces list, its search replaces prompt
+                    self.logger.history(|history| self.log_view.update(history));
+                    let hint = format!(
+                        "{} {}/{}",
+                        self.log_view.level().as_str().to_lowercase(),
+                        self.log_view.matched_len(),
+                        self.log_view.len()
+                    );
+                    let prompt = self.log_view.prompt();
+                    frame.render_widget(
+                        ui::PromtWidget::new(prompt, &hint, self.theme.rule),
+                        prompt_area,
+                    );
+                    frame.set_cursor_position((
+                        prompt_area.x + prompt.cursor() as u16,
+                        prompt_area.y,
+                    ));
+                    frame.render_stateful_widget(
+                        ui::LogViewWidget::new(self.theme.border),
+                        list_area,
+                        &mut self.log_view,
+                    );
+                } else {
+                    let mut hint = format!("{}/{}", self.list.matched_len(), self.list.len());
+                    if self.list.marked_len() > 0 {
+                        hint += &format!(" ({})", self.list.marked_len());
+                    }
+                    frame.render_widget(
+                        ui::PromtWidget::new(&self.prompt, &hint, self.theme.rule),
+                        prompt_area,
+                    );
+                    frame.set_cursor_position((
+                        prompt_area.x + self.prompt.cursor() as u16,
+                        prompt_area.y,
+                    ));
+                    frame.render_widget(
+                        ui::SessionListWidget::new(&self.list, &self.theme),
+                        list_area,
+                    );
+                }
 
                 // display help
                 if self.help {
-                    frame.render_widget(ui::HelpWidget::new(), area);
+                    frame.render_stateful_widget(
+                        ui::HelpWidget::new(self.theme.border),
+                        area,
+                        &mut self.help_view,
+                    );
                 }
 
-                // display messages
+                // display messages, log view shows them anyway
+                if self.logs {
+                    return;
+                }
                 let layout = Layout::horizontal([
                     Constraint::Fill(1),
                     Constraint::Percentage(30),
@@ -85,73 +268,590 @@ impl Application {
         } else {
             self.terminal.show_cursor().unwrap();
         }
+
+        // messages logged while rendering will be shown next time
+        self.rendered_messages = self.logger.count();
+        self.dirty = false;
     }
 
+    /// Waits for input, matcher results, new messages or timers and handles them
     pub fn update(&mut self) {
-        while event::poll(std::time::Duration::from_millis(10)).unwrap() {
-            let e = event::read().unwrap();
-
-            if self.prompt.handle_event(&e).value {
-                self.list.prompt(self.prompt.value());
-                return;
+        let timeout = self.deadline().saturating_duration_since(Instant::now());
+        let mut event = self.events.next(timeout);
+        while let Some(e) = event {
+            match e {
+                events::Event::Input(e) => {
+                    self.handle_input(&e);
+                    // mouse motion is reported too, but it changes nothing
+                    self.dirty |= !matches!(e, Event::Mouse(m) if m.kind == MouseEventKind::Moved);
+                }
+                events::Event::Matcher => (),
+                events::Event::Refresh => self.next_refresh = Instant::now(),
+                events::Event::Source(i, sessions) => self.update_source(i, sessions),
+                events::Event::Message => {
+                    self.dirty |= self.logger.count() != self.rendered_messages
+                }
             }
-            if self.list.handle_event(&e) {
-                return;
+            if self.selected.is_some() || !self.running {
+                break;
             }
-            match e.as_key_press_event() {
-                Some(e) => match e.code {
-                    KeyCode::Esc => self.running = false,
-                    KeyCode::Char('c') if e.modifiers == KeyModifiers::CONTROL => {
-                        self.running = false
-                    }
-                    KeyCode::Char('7') if e.modifiers == KeyModifiers::CONTROL => {
-                        self.help = !self.help
-                    }
-                    KeyCode::Char('x') if e.modifiers == KeyModifiers::CONTROL => {
-                        match self.list.selected() {
-                            Some((name, session)) => tmux::kill(&name.to_string(), &session),
-                            _ => (),
-                        }
-                    }
-                    KeyCode::Enter => {
-                        self.selected = self.list.selected().map(|(n, s)| (n.clone(), s.clone()))
+            event = self.events.try_next();
+        }
+
+        if Instant::now() >= self.next_refresh {
+            self.refresh();
+        }
+        if self.next_snapshot.is_some_and(|t| Instant::now() >= t) {
+            self.save(false);
+        }
+        self.dirty |= self.list.tick();
+        self.dirty |= self
+            .logger
+            .next_expiry()
+            .is_some_and(|t| t <= chrono::Local::now());
+    }
+
+    /// Stops reading input, so that tmux may take over terminal
+    pub fn suspend(&mut self) {
+        self.capture_mouse(false);
+        self.events.stop_input();
+    }
+
+    /// Resumes after `suspend`
+    pub fn resume(&mut self) {
+        self.capture_mouse(true);
+        self.events.start_input();
+        self.next_refresh = Instant::now();
+        self.dirty = true;
+    }
+
+    fn handle_input(&mut self, e: &Event) {
+        if let Event::Mouse(m) = e
+            && m.kind == MouseEventKind::Down(MouseButton::Left)
+        {
+            self.click(m.column, m.row);
+            return;
+        }
+        // help, which does not fit screen, is scrolled instead of list
+        if self.help && self.help_view.handle_event(e) {
+            return;
+        }
+        if self.logs {
+            self.handle_log_input(e);
+            return;
+        }
+        if self.prompt.handle_event(e).value {
+            self.list.prompt(self.prompt.value());
+            return;
+        }
+        if self.list.handle_event(e) {
+            return;
+        }
+        if let Some(e) = e.as_key_press_event() {
+            match e.code {
+                KeyCode::Esc => self.running = false,
+                KeyCode::Char('c') if e.modifiers == KeyModifiers::CONTROL => self.running = false,
+                KeyCode::Char('7') if e.modifiers == KeyModifiers::CONTROL => {
+                    self.help = !self.help
+                }
+                KeyCode::Char('x') if e.modifiers == KeyModifiers::CONTROL => {
+                    self.batch("kill", |name, session| Some(tmux::kill(name, session)));
+                    self.next_refresh = Instant::now();
+                }
+                KeyCode::Char('l') if e.modifiers == KeyModifiers::CONTROL => self.logs = true,
+                KeyCode::Char('s') if e.modifiers == KeyModifiers::CONTROL => self.save(true),
+                KeyCode::Char('e') if e.modifiers == KeyModifiers::ALT => self.export(),
+                KeyCode::Char('r') if e.modifiers == KeyModifiers::CONTROL => {
+                    self.restore();
+                    self.next_refresh = Instant::now();
+                }
+                KeyCode::Char('v') if e.modifiers == KeyModifiers::ALT => {
+                    self.selected = self.list.selected().map(|(n, mut s)| {
+                        s.view = true;
+                        (n.clone(), s, tmux::Mode::Switch)
+                    })
+                }
+                KeyCode::Char('o') if e.modifiers == KeyModifiers::ALT => {
+                    self.selected = self.list.selected().map(|(n, mut s)| {
+                        s.detach = true;
+                        (n.clone(), s, tmux::Mode::Switch)
+                    })
+                }
+                KeyCode::Char('r') if e.modifiers == KeyModifiers::ALT => {
+                    self.selected = self.list.selected().map(|(n, mut s)| {
+                        s.read_only = true;
+                        (n.clone(), s, tmux::Mode::Switch)
+                    })
+                }
+                KeyCode::Char('l') if e.modifiers == KeyModifiers::ALT => {
+                    self.select(tmux::Mode::Link)
+                }
+                KeyCode::Char('p') if e.modifiers == KeyModifiers::ALT => {
+                    self.select(tmux::Mode::Popup)
+                }
+                KeyCode::Char('t') if e.modifiers == KeyModifiers::ALT => {
+                    self.select(tmux::Mode::Terminal(self.terminal_command.clone()))
+                }
+                KeyCode::Enter => {
+                    // create marked sessions in background, selected one is opened later
+                    // sessions of other servers may have the same name
+                    let selected = self.list.selected().map(|(n, s)| (s.server, n.to_string()));
+                    if self.list.marked_len() > 0 {
+                        self.batch("create", |name, session| {
+                            let key = (session.server.clone(), name.clone());
+                            (Some(&key) != selected.as_ref() && session.state == ui::State::None)
+                                .then(|| tmux::create_session(name, session))
+                        });
+                        self.next_refresh = Instant::now();
                     }
-                    _ => (),
-                },
+                    self.select(tmux::Mode::Switch)
+                }
                 _ => (),
             }
         }
-        self.refresh();
     }
 
-    pub fn selected(&mut self) -> Option<(ui::MatchedString, ui::Session)> {
-        std::mem::replace(&mut self.selected, None)
+    /// Closes help when clicked outside of it, selects clicked session, opens it on double click
+    fn click(&mut self, x: u16, y: u16) {
+        if self.help {
+            let help = ui::HelpWidget::new(self.theme.border);
+            if help
+                .area(self.screen)
+                .is_none_or(|a| !a.contains(Position::new(x, y)))
+            {
+                self.help = false;
+            }
+            return;
+        }
+        let clicked = match self.logs {
+            true => None,
+            false => self.list.click(self.list_area, y),
+        };
+        let Some(clicked) = clicked else {
+            self.last_click = None;
+            return;
+        };
+        // list scrolls after the first click, so the second one must hit the same session
+        let now = Instant::now();
+        let double = self
+            .last_click
+            .as_ref()
+            .is_some_and(|(t, last)| *last == clicked && now - *t <= DOUBLE_CLICK);
+        self.last_click = (!double).then_some((now, clicked));
+        if double {
+            self.select(tmux::Mode::Switch);
+        }
+    }
+
+    /// Handles input of log view, which is closed with `esc` or `ctrl+l`
+    fn handle_log_input(&mut self, e: &Event) {
+        if self.log_view.handle_event(e) {
+            return;
+        }
+        if let Some(e) = e.as_key_press_event() {
+            match e.code {
+                KeyCode::Esc => self.logs = false,
+                KeyCode::Char('l') if e.modifiers == KeyModifiers::CONTROL => self.logs = false,
+                KeyCode::Char('c') if e.modifiers == KeyModifiers::CONTROL => self.running = false,
+                KeyCode::Char('7') if e.modifiers == KeyModifiers::CONTROL => {
+                    self.help = !self.help
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn select(&mut self, mode: tmux::Mode) {
+        self.selected = self.list.selected().map(|(n, s)| (n, s, mode))
+    }
+
+    pub fn selected(&mut self) -> Option<(ui::MatchedString, ui::Session, tmux::Mode)> {
+        self.selected.take()
     }
 
     pub fn finish(&self) {
+        self.capture_mouse(false);
         ratatui::restore();
     }
 
+    /// Enables or disables mouse capture, if mouse is enabled in config
+    fn capture_mouse(&self, enable: bool) {
+        if !self.ui.mouse {
+            return;
+        }
+        let result = match enable {
+            true => crossterm::execute!(std::io::stdout(), crossterm::event::EnableMouseCapture),
+            false => crossterm::execute!(std::io::stdout(), crossterm::event::DisableMouseCapture),
+        };
+        if let Err(e) = result {
+            log::error!("Failed to set mouse capture: {}", e);
+        }
+    }
+
+    /// Runs action on all marked sessions, or on selected one if nothing is marked
+    /// Action returns None if it is not applicable to session, otherwise whether it succeeded
+    /// For marked sessions, summary is reported and succeeded ones are unmarked
+    fn batch<F: FnMut(&String, &ui::Session) -> Option<bool>>(&mut self, action: &str, mut f: F) {
+        if self.list.marked_len() == 0 {
+            if let Some((name, session)) = self.list.selected() {
+                f(&name.to_string(), &session);
+            }
+            return;
+        }
+
+        let (mut succeeded, mut failed) = (Vec::new(), Vec::new());
+        for (name, session) in self.list.marked_items() {
+            match f(&name, &session) {
+                Some(true) => {
+                    self.list.unmark(&session.server, &name);
+                    succeeded.push(name)
+                }
+                Some(false) => failed.push(name),
+                None => (),
+            }
+        }
+        report(action, &succeeded, &failed);
+    }
+
+    /// Opens control mode connection, so that state changes are noticed immediately
+    fn connect(&self) {
+        let Some(session) = tmux::Tmux::current_session() else {
+            log::warn!("Control mode is available only inside of tmux");
+            return;
+        };
+        let tx = self.events.sender();
+        let notify = move |n: tmux::control::Notification| {
+            log::debug!("tmux notification: {:?}", n);
+            if let tmux::control::Notification::Exit(reason) = n {
+                log::warn!("tmux control mode exited: {}", reason.unwrap_or_default());
+            }
+            let _ = tx.send(events::Event::Refresh);
+        };
+        if let Err(e) = tmux::control::connect(&session, notify) {
+            log::error!("Failed to start tmux control mode: {}", e);
+        }
+    }
+
+    /// Returns time, when something should be updated even without events
+    fn deadline(&self) -> Instant {
+        let expiry = self
+            .logger
+            .next_expiry()
+            .map(|t| Instant::now() + (t - chrono::Local::now()).to_std().unwrap_or_default());
+        [expiry, self.next_snapshot]
+            .into_iter()
+            .flatten()
+            .fold(self.next_refresh, Instant::min)
+    }
+
+    /// Saves snapshot of running sessions, `verbose` reports success too
+    /// Each session and window is queried separately, so it is done in background
+    fn save(&mut self, verbose: bool) {
+        let interval = self.snapshot.interval.and_then(|i| i.to_std().ok());
+        self.next_snapshot = interval.map(|i| Instant::now() + i);
+        if self.saving.swap(true, Ordering::AcqRel) {
+            log::debug!("Snapshot is being saved already");
+            return;
+        }
+        let (path, servers, saving) = (
+            self.snapshot.path.clone(),
+            self.servers.clone(),
+            self.saving.clone(),
+        );
+        std::thread::spawn(move || {
+            match snapshot::save(&path, &servers) {
+                Ok(0) if verbose => log::warn!("No sessions to save"),
+                Ok(n) if verbose => log::info!("Saved {} sessions", n),
+                Ok(n) => log::debug!("Saved {} sessions", n),
+                Err(e) => log::error!("Failed to save snapshot: {:#}", e),
+            }
+            saving.store(false, Ordering::Release);
+        });
+    }
+
+    /// Appends selected running session to config
+    fn export(&self) {
+        let Some((name, session)) = self.list.selected() else {
+            return;
+        };
+        if session.state == ui::State::None {
+            log::warn!("Unable to export {} because it is not created", name);
+            return;
+        }
+        let name = name.to_string();
+        match snapshot::export(&name, &session.server).and_then(|s| append(&self.config_path, &s)) {
+            Ok(()) => log::info!("Exported {} to {}", name, self.config_path.display()),
+            Err(e) => log::error!("Failed to export {}: {:#}", name, e),
+        }
+    }
+
+    /// Recreates sessions from snapshot
+    fn restore(&mut self) {
+        match snapshot::restore(&self.snapshot.path) {
+            Ok((restored, failed)) => report("restore", &restored, &failed),
+            Err(e) => log::error!("Failed to restore snapshot: {:#}", e),
+        }
+    }
+
     fn refresh(&mut self) {
-        let selected = self.list.get_selected_index();
-        self.list = ui::SessionList::new();
-        let (attached, opened) = tmux::list_sessions();
-        let map = HashMap::<String, _>::from_iter(self.config.iter().map(|s| (s.name.clone(), s)));
+        self.next_refresh = Instant::now() + self.refresh_interval;
+        let (mut states, mut views) = (HashMap::new(), HashMap::new());
+        for server in self.servers.iter() {
+            let sessions = match tmux::list_sessions(server) {
+                Ok(sessions) => sessions,
+                Err(e) => {
+                    log::error!("Failed to list sessions of {} server: {:#}", server, e);
+                    continue;
+                }
+            };
+            // views, including groups created outside of tiramisu, are shown as part of session they view
+            let names: HashSet<_> = sessions.iter().map(|s| s.name.clone()).collect();
+            let (server_states, server_views) = (
+                states.entry(server.clone()).or_insert(HashMap::new()),
+                views.entry(server.clone()).or_insert(HashMap::new()),
+            );
+            for s in sessions.iter() {
+                let name = match s.viewed(&names) {
+                    Some(viewed) => {
+                        *server_views.entry(viewed.to_string()).or_insert(0) += 1;
+                        viewed
+                    }
+                    None => &s.name,
+                };
+                let state = server_states
+                    .entry(name.to_string())
+                    .or_insert(ui::State::Created);
+                if s.attached > 0 {
+                    *state = ui::State::Attached;
+                }
+            }
+        }
+        self.list.set_states(states, views);
+
+        if !self.discovered.load(Ordering::Acquire) {
+            return;
+        }
+        // sources run commands and check lots of directories, so they are read in background
+        let now = Instant::now();
+        for (i, (source, next, _)) in self.sources.iter_mut().enumerate() {
+            if next.is_none_or(|t| t > now) {
+                continue;
+            }
+            *next = None;
+            let (source, tx) = (source.clone(), self.events.sender());
+            std::thread::spawn(move || {
+                let sessions = source::sessions(&source)
+                    .inspect_err(|e| log::error!("Failed to read directories: {:#}", e))
+                    .ok();
+                let _ = tx.send(events::Event::Source(i, sessions));
+            });
+        }
+    }
+
+    /// Lists sessions read by source, sessions of previous read are replaced
+    fn update_source(&mut self, i: usize, sessions: Option<Vec<config::Session>>) {
+        let (source, next, listed) = &mut self.sources[i];
+        *next = Some(Instant::now() + source.refresh_interval.to_std().unwrap_or_default());
+        let Some(sessions) = sessions else {
+            return;
+        };
+        // directories of configured sessions are already listed
+        let ssh = matches!(source.kind, config::SourceKind::Ssh { .. });
+        let mut current = HashSet::new();
+        for session in sessions {
+            let key = (session.server.clone(), session.name.clone());
+            // sessions listed by source before are kept, removed ones are listed again
+            let own = listed.contains(&key) || self.list.restore(&key.0, &key.1);
+            if own
+                || ((ssh || !self.injector.has_root(&session.root)) && self.injector.push(session))
+            {
+                current.insert(key);
+            }
+        }
+        // directories, which are not produced by source anymore
+        for (server, name) in listed.difference(&current) {
+            self.list.remove(server, name);
+        }
+        *listed = current;
+    }
+}
+
+/// Reports result of action applied to several sessions
+fn report(action: &str, succeeded: &[String], failed: &[String]) {
+    if !succeeded.is_empty() {
+        log::info!(
+            "{action}: {} succeeded ({})",
+            succeeded.len(),
+            succeeded.join(", ")
+        );
+    }
+    if !failed.is_empty() {
+        log::warn!("{action}: {} failed ({})", failed.len(), failed.join(", "));
+    }
+}
+
+/// Appends text to file, separating it with empty line
+fn append(path: &std::path::Path, text: &str) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    write!(file, "\n{}", text)?;
+    Ok(())
+}
 
-        for name in attached {
-            map.get(&name)
-                .inspect(|s| self.list.insert(s, ui::State::Attached));
+/// Exports running session to stdout or appends it to file
+pub fn export(
+    config: &config::Config,
+    name: &str,
+    output: Option<&std::path::Path>,
+) -> anyhow::Result<()> {
+    let _logger = Logger::new(&config.logger, std::sync::Arc::new(|| ()))?;
+    let server = config
+        .servers()
+        .into_iter()
+        .find(|server| tmux::list_sessions(server).is_ok_and(|s| s.iter().any(|s| s.name == name)))
+        .ok_or(anyhow::anyhow!("Session {} is not running", name))?;
+    let text = snapshot::export(name, &server)?;
+    match output {
+        Some(path) => append(path, &text),
+        None => {
+            print!("{}", text);
+            Ok(())
         }
+    }
+}
 
-        for name in opened {
-            map.get(&name)
-                .inspect(|s| self.list.insert(s, ui::State::Created));
+/// Converts project files of other session managers to config entries, prints them
+pub fn convert(config: &config::Config, paths: &[std::path::PathBuf]) -> anyhow::Result<()> {
+    let _logger = Logger::new(&config.logger, std::sync::Arc::new(|| ()))?;
+    let mut sessions = Vec::new();
+    for path in paths {
+        let imported = import::read(path).map_err(|e| e.context(path.display().to_string()))?;
+        for warning in imported.warnings {
+            log::warn!("{}: {}", path.display(), warning);
+            eprintln!("{}: {}", path.display(), warning);
         }
+        sessions.push(imported.session);
+    }
+    print!("{}", import::to_toml(&sessions)?);
+    Ok(())
+}
+
+/// Saves snapshot without picker
+pub fn save(config: &config::Config) -> anyhow::Result<()> {
+    let _logger = Logger::new(&config.logger, std::sync::Arc::new(|| ()))?;
+    let n = snapshot::save(&config.snapshot.path, &config.servers())?;
+    println!("Saved {} sessions to {}", n, config.snapshot.path.display());
+    Ok(())
+}
+
+/// Restores snapshot without picker
+pub fn restore(config: &config::Config) -> anyhow::Result<()> {
+    let _logger = Logger::new(&config.logger, std::sync::Arc::new(|| ()))?;
+    let (restored, failed) = snapshot::restore(&config.snapshot.path)?;
+    println!("Restored {} sessions", restored.len());
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "Failed to restore {}, see logs for details",
+            failed.join(", ")
+        );
+    }
+    Ok(())
+}
 
-        for session in self.config.iter() {
-            self.list.insert(session, ui::State::None);
+/// Opens session by name without picker, optionally at given window,
+/// detaching its other clients or read-only
+pub fn open(
+    config: &config::Config,
+    name: &str,
+    window: Option<String>,
+    detach: bool,
+    read_only: bool,
+) -> anyhow::Result<()> {
+    let _logger = Logger::new(&config.logger, std::sync::Arc::new(|| ()))?;
+    let session = config
+        .session
+        .iter()
+        .filter_map(|s| s.expand().ok())
+        .flatten()
+        .filter_map(|s| s.ok())
+        .find(|s| s.name == name)
+        .ok_or(anyhow::anyhow!("No session {:?} in config", name))?;
+
+    let attached = tmux::list_sessions(&session.server)?
+        .into_iter()
+        .find(|s| s.name == name)
+        .map(|s| s.attached > 0);
+    let state = match attached {
+        None => ui::State::None,
+        Some(false) => ui::State::Created,
+        Some(true) => ui::State::Attached,
+    };
+    let mut session = ui::Session::new(&session, state);
+    session.detach = detach;
+    session.read_only = read_only;
+    if let Some(window) = window {
+        session.focus = Some(config::Focus {
+            window,
+            pane: None,
+            always: true,
+        });
+    }
+    if !tmux::open(name, &session, &tmux::Mode::Switch) {
+        anyhow::bail!("Failed to open {}, see logs for details", name);
+    }
+    Ok(())
+}
+
+/// Expands configured sessions and streams them into list
+fn discover(sessions: Vec<config::Session>, injector: &ui::SessionInjector) {
+    for session in sessions.iter() {
+        let expanded = match session.expand() {
+            Ok(expanded) => expanded,
+            Err(e) => {
+                log::error!("Failed to expand {}: {}", session.name, e);
+                continue;
+            }
+        };
+        for s in expanded {
+            match s {
+                Ok(s) => {
+                    injector.push(s);
+                }
+                Err(e) => log::error!("Failed to expand {}: {}", session.name, e),
+            }
+        }
+    }
+}
+
+/// Reads project files of other session managers, unsupported fields are reported
+fn import_sessions(patterns: &[String], server: &config::Server) -> Vec<config::Session> {
+    let mut sessions = Vec::new();
+    for pattern in patterns {
+        let paths = match config::import_paths(pattern) {
+            Ok(paths) => paths,
+            Err(e) => {
+                log::error!("Failed to import {}: {}", pattern, e);
+                continue;
+            }
+        };
+        for path in paths {
+            let imported = path.and_then(|path| {
+                let imported = import::read(&path);
+                imported.map_err(|e| e.context(path.display().to_string()))
+            });
+            match imported {
+                Ok(mut imported) => {
+                    for warning in imported.warnings {
+                        log::warn!("{}: {}", imported.session.name, warning);
+                    }
+                    imported.session.server = server.clone();
+                    sessions.push(imported.session);
+                }
+                Err(e) => log::error!("Failed to import: {:#}", e),
+            }
         }
-        self.list.set_selected(selected);
-        self.list.prompt(self.prompt.value());
     }
+    sessions
 }
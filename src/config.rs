@@ -1,10 +1,13 @@
-use crate::paths;
+use crate::{paths, tmux};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_inline_default::serde_inline_default;
 use serde_valid::Validate;
 use serde_with::{DisplayFromStr, DurationSeconds, serde_as};
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 #[serde_inline_default]
 #[derive(Debug, Deserialize, Validate)]
@@ -13,11 +16,223 @@ pub struct Config {
     pub logger: Logger,
 
     /// List of sessions
+    #[serde(default)]
     pub session: Vec<Session>,
 
+    /// tmuxinator and tmuxp project files (globs), listed as sessions
+    #[serde(default)]
+    pub import: Vec<String>,
+
+    /// Directories visited recently (zoxide database or list file), listed as sessions
+    #[serde(default)]
+    #[validate]
+    pub source: Vec<Source>,
+
     /// Whether to show initial help message
     #[serde_inline_default(true)]
     pub show_help: bool,
+
+    /// Search configuration
+    #[serde(default)]
+    #[validate]
+    pub search: Search,
+
+    /// Tmux configuration
+    #[serde(default)]
+    #[validate]
+    pub tmux: Tmux,
+
+    /// Snapshots of running sessions
+    #[serde(default)]
+    #[validate]
+    pub snapshot: Snapshot,
+
+    /// Picker appearance and input
+    #[serde(default)]
+    pub ui: Ui,
+
+    /// Path config was read from
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+#[serde_inline_default]
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct Source {
+    /// Where directories are read from
+    #[serde(flatten)]
+    pub kind: SourceKind,
+
+    /// Prefix of session names, name is directory path with `~` for home directory or host,
+    /// which is also `$1` in windows
+    #[serde(default)]
+    pub prefix: String,
+
+    /// Maximum number of directories, all by default
+    pub limit: Option<usize>,
+
+    /// Tags of all sessions
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// How often to read directories again (in seconds)
+    #[serde_as(as = "DurationSeconds<f64>")]
+    #[serde_inline_default(chrono::Duration::seconds(30))]
+    #[validate(custom = positive)]
+    pub refresh_interval: chrono::Duration,
+
+    /// Server sessions live in, `[tmux]` one by default
+    #[serde(flatten)]
+    pub server: Server,
+
+    /// Windows of all sessions, single shell (or `ssh $1`) by default
+    #[serde(default)]
+    pub window: Vec<Window>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SourceKind {
+    /// Output of `zoxide query -l`, most frecent first
+    Zoxide,
+    /// File with directory per line
+    List { path: PathBuf },
+    /// Hosts of ssh config, sessions are started in home directory
+    Ssh {
+        /// Path of ssh config
+        #[serde(default = "paths::ssh_config")]
+        config: PathBuf,
+        /// Whether to list hosts of `~/.ssh/known_hosts` too
+        #[serde(default)]
+        known_hosts: bool,
+    },
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize, Validate)]
+#[serde(default)]
+pub struct Snapshot {
+    /// Snapshot path
+    pub path: PathBuf,
+
+    /// How often to save snapshot while picker runs (in seconds), never by default
+    #[serde_as(as = "Option<DurationSeconds<f64>>")]
+    #[validate(custom = positive_or_none)]
+    pub interval: Option<chrono::Duration>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(default)]
+pub struct Ui {
+    /// Whether to capture mouse: click selects session, double click opens it,
+    /// wheel moves selection
+    pub mouse: bool,
+
+    /// Order of sessions, best match is next to prompt by default (bottom-up top-down)
+    pub orientation: Orientation,
+
+    /// Where prompt is shown (top bottom)
+    pub prompt: PromptPosition,
+
+    /// Whether to draw border around picker
+    pub border: bool,
+
+    /// Whether to show header with config path and default tmux server
+    pub header: bool,
+
+    /// Empty columns on both sides of session rows
+    pub padding: u16,
+
+    /// Whether to use ASCII glyphs and borders, for fonts without box drawing characters
+    pub ascii: bool,
+
+    /// Glyphs of session rows, defaults depend on `ascii`
+    pub glyphs: Glyphs,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Orientation {
+    /// Best match is at the bottom, fzf style
+    #[default]
+    BottomUp,
+    TopDown,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptPosition {
+    Top,
+    #[default]
+    Bottom,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Glyphs {
+    /// Mark of selected row (`▌` or `>`)
+    pub selected: Option<String>,
+    /// Mark of other rows (`▎` or ` `)
+    pub unselected: Option<String>,
+    /// Session, which is created, but not attached (`◇` or `o`)
+    pub created: Option<String>,
+    /// Attached session (`◆` or `*`)
+    pub attached: Option<String>,
+    /// Marked session (`+` in both cases)
+    pub marked: Option<String>,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize, Validate)]
+#[serde(default)]
+pub struct Tmux {
+    /// How often to poll tmux for session states (in seconds)
+    #[serde_as(as = "DurationSeconds<f64>")]
+    #[validate(custom = positive)]
+    pub refresh_interval: chrono::Duration,
+
+    /// Whether to talk to tmux over persistent control mode connection
+    pub control_mode: bool,
+
+    /// External terminal command sessions are opened in with `alt+t`,
+    /// `{}` is replaced with attach command, e.g. `alacritty -e {}`
+    #[validate(custom = placeholder)]
+    pub terminal: String,
+
+    /// Default server for all sessions
+    #[serde(flatten)]
+    pub server: Server,
+}
+
+/// Tmux server, if neither of options is set, default one is used
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Server {
+    /// Socket name (`tmux -L`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket_name: Option<String>,
+
+    /// Socket path (`tmux -S`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(default)]
+pub struct Search {
+    /// Session fields prompt is matched against (name root windows tags description)
+    #[validate(min_items = 1)]
+    pub fields: Vec<SearchField>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchField {
+    Name,
+    Root,
+    Windows,
+    Tags,
+    Description,
 }
 
 #[serde_inline_default]
@@ -36,9 +251,34 @@ pub struct Logger {
     /// Log path
     #[serde(default = "paths::logs")]
     pub log_path: std::path::PathBuf,
+
+    /// Log file format (text json)
+    #[serde(default)]
+    pub format: LogFormat,
+
+    /// Log file is rotated once it grows bigger (in bytes), never by default
+    pub max_size: Option<u64>,
+
+    /// Log file is rotated once it gets older (in seconds), never by default
+    #[serde_as(as = "Option<DurationSeconds<f64>>")]
+    pub max_age: Option<chrono::Duration>,
+
+    /// How many rotated files to keep, they are named `<log_path>.1`, `<log_path>.2`...
+    #[serde_inline_default(5)]
+    pub keep: usize,
 }
 
-#[derive(Debug, Clone, Deserialize, Validate)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// `2024-01-01 00:00:00 [INFO] message`
+    #[default]
+    Text,
+    /// JSON object per line with level, target, module path and structured fields
+    Json,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
 pub struct Session {
     /// Session root dir, may be glob
     pub root: PathBuf,
@@ -46,75 +286,410 @@ pub struct Session {
     /// Session name
     pub name: String,
 
+    /// Free-form tags, may be used in search as `tag:name`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// Free-form description
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+
+    /// Server session lives in, `[tmux]` one by default
+    #[serde(flatten)]
+    pub server: Server,
+
+    /// Environment variables of all windows, also set in session environment
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+
+    /// Session options, set right after creation
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, String>,
+
+    /// Arbitrary tmux commands, run after session and all windows are created
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tmux_commands: Vec<String>,
+
+    /// Whether to open session, which is attached elsewhere, through grouped view session,
+    /// so that each client has its own current window
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub view: bool,
+
+    /// Window to focus, by default the first one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus: Option<Focus>,
+
     /// List of windows
     #[validate(min_items = 1)]
     pub window: Vec<Window>,
 }
 
-#[derive(Debug, Clone, Deserialize, Validate)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Focus {
+    /// Window name or index (in order of creation)
+    pub window: String,
+
+    /// Pane index (in order of creation)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pane: Option<usize>,
+
+    /// Whether to focus it on every switch, not only after creation
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub always: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
 pub struct Window {
     /// Window name
     pub name: String,
 
     /// Window startup command
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub command: String,
 
+    /// Working directory, relative to session root
+    #[serde(default, skip_serializing_if = "is_empty_path")]
+    pub dir: PathBuf,
+
+    /// Window environment variables
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+
+    /// Window options, set right after creation
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, String>,
+
     /// Command to send to safely kill window
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub kill: Vec<String>,
+
+    /// Container window and its panes run in: container name, `devcontainer` (container of
+    /// session root devcontainer) or `compose:<service>` (service of session root compose project)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub container: String,
+
+    /// Layout of panes, preset (`tiled`, `main-vertical`, ...) or tmux layout string
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub layout: String,
+
+    /// Additional panes, split from the first one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pane: Vec<Pane>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct Pane {
+    /// Pane startup command
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub command: String,
+
+    /// Working directory, relative to window one
+    #[serde(default, skip_serializing_if = "is_empty_path")]
+    pub dir: PathBuf,
+}
+
+impl std::fmt::Display for SearchField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SearchField::Name => "name",
+            SearchField::Root => "root",
+            SearchField::Windows => "windows",
+            SearchField::Tags => "tags",
+            SearchField::Description => "description",
+        })
+    }
+}
+
+impl Server {
+    /// Whether it is default server
+    pub fn is_default(&self) -> bool {
+        self.socket_name.is_none() && self.socket_path.is_none()
+    }
+
+    /// Returns tmux arguments selecting this server
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(name) = &self.socket_name {
+            args.extend(["-L".to_string(), name.clone()]);
+        }
+        if let Some(path) = &self.socket_path {
+            args.extend(["-S".to_string(), path.to_string_lossy().to_string()]);
+        }
+        args
+    }
+}
+
+impl std::fmt::Display for Server {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.socket_name, &self.socket_path) {
+            (_, Some(path)) => write!(f, "{}", path.display()),
+            (Some(name), None) => f.write_str(name),
+            (None, None) => f.write_str("default"),
+        }
+    }
+}
+
+impl Default for Tmux {
+    fn default() -> Self {
+        Self {
+            refresh_interval: chrono::Duration::seconds(1),
+            control_mode: false,
+            terminal: String::new(),
+            server: Server::default(),
+        }
+    }
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Self {
+            mouse: true,
+            orientation: Orientation::default(),
+            prompt: PromptPosition::default(),
+            border: false,
+            header: false,
+            padding: 0,
+            ascii: false,
+            glyphs: Glyphs::default(),
+        }
+    }
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self {
+            path: paths::snapshot(),
+            interval: None,
+        }
+    }
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            fields: vec![SearchField::Name],
+        }
+    }
 }
 
 impl Config {
-    /// Reads and preprocesses configuration
+    /// Reads and validates configuration, sessions are expanded lazily with `Session::expand`
     pub fn new<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
-        let mut config: Config = toml::from_str(&std::fs::read_to_string(path)?)?;
+        let mut config: Config = toml::from_str(&std::fs::read_to_string(&path)?)?;
         config.validate()?;
-        config.expand()?;
+        config.path = path.as_ref().to_path_buf();
+        for session in config.session.iter_mut() {
+            if session.server.is_default() {
+                session.server = config.tmux.server.clone();
+            }
+        }
+        for source in config.source.iter_mut() {
+            if source.server.is_default() {
+                source.server = config.tmux.server.clone();
+            }
+        }
+        // expansion is lazy, but glob patterns are checked here
+        for session in config.session.iter() {
+            let _ = session.expand()?;
+        }
+        for pattern in config.import.iter() {
+            let _ = import_paths(pattern)?;
+        }
         Ok(config)
     }
 
-    // This code is cursed
-    fn expand(&mut self) -> anyhow::Result<()> {
-        let mut sessions: Vec<Session> = Vec::new();
-        for session in self.session.iter() {
-            for entry in capturing_glob::glob(&replace_env(session.root.to_str().unwrap(), None))? {
-                match entry {
-                    Ok(e) if e.path().is_dir() => sessions.push(Session {
-                        root: e.path().to_path_buf(),
-                        name: replace_env(&session.name, Some(&e)),
-                        window: session
-                            .window
-                            .iter()
-                            .map(|w| Window {
-                                name: replace_env(&w.name, Some(&e)),
-                                command: replace_env(&w.command, Some(&e)),
-                                kill: w.kill.clone(),
-                            })
-                            .collect(),
-                    }),
-                    Err(e) => return Err(e.into()),
-                    _ => continue,
-                }
+    /// Returns list of distinct servers used by sessions
+    pub fn servers(&self) -> Vec<Server> {
+        let mut servers: Vec<Server> = Vec::new();
+        let sessions = self.session.iter().map(|s| &s.server);
+        let sources = self.source.iter().map(|s| &s.server);
+        for server in std::iter::once(&self.tmux.server)
+            .chain(sessions)
+            .chain(sources)
+        {
+            if !servers.contains(server) {
+                servers.push(server.clone());
             }
         }
-        self.session = sessions;
+        servers
+    }
+}
+
+impl Session {
+    /// Expands root glob, yields session for each matched directory
+    pub fn expand(&self) -> anyhow::Result<impl Iterator<Item = anyhow::Result<Session>> + '_> {
+        let paths = capturing_glob::glob(&replace_env(self.root.to_str().unwrap(), None))?;
+        Ok(paths.filter_map(|entry| match entry {
+            Ok(e) if e.path().is_dir() => Some(self.expand_entry(&e)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e.into())),
+        }))
+    }
 
-        Ok(())
+    fn expand_entry(&self, e: &capturing_glob::Entry) -> anyhow::Result<Session> {
+        self.instantiate(e.path(), e)
     }
+
+    /// Creates session in given root, `$n` in fields is replaced with n-th group
+    /// Name is sanitized, renamed session is reported
+    // This code is cursed
+    pub fn instantiate(&self, root: &Path, e: &dyn Groups) -> anyhow::Result<Session> {
+        let raw = replace_env(&self.name, Some(e));
+        let name = tmux::target::sanitize(&raw)
+            .map_err(|err| anyhow::anyhow!("{} (root {})", err, root.display()))?;
+        if name != raw {
+            log::warn!("Session {:?} is renamed to {:?}", raw, name);
+        }
+        Ok(Session {
+            root: root.to_path_buf(),
+            name,
+            tags: self.tags.iter().map(|t| replace_env(t, Some(e))).collect(),
+            description: replace_env(&self.description, Some(e)),
+            server: Server {
+                socket_name: self
+                    .server
+                    .socket_name
+                    .as_ref()
+                    .map(|s| replace_env(s, Some(e))),
+                socket_path: self
+                    .server
+                    .socket_path
+                    .as_ref()
+                    .map(|s| replace_env(s.to_str().unwrap(), Some(e)).into()),
+            },
+            env: replace_env_map(&self.env, e),
+            options: replace_env_map(&self.options, e),
+            tmux_commands: self
+                .tmux_commands
+                .iter()
+                .map(|c| replace_env(c, Some(e)))
+                .collect(),
+            view: self.view,
+            focus: self.focus.as_ref().map(|f| Focus {
+                window: replace_env(&f.window, Some(e)),
+                ..f.clone()
+            }),
+            window: self
+                .window
+                .iter()
+                .map(|w| Window {
+                    name: replace_env(&w.name, Some(e)),
+                    command: replace_env(&w.command, Some(e)),
+                    dir: replace_env(w.dir.to_str().unwrap(), Some(e)).into(),
+                    env: replace_env_map(&w.env, e),
+                    options: replace_env_map(&w.options, e),
+                    kill: w.kill.clone(),
+                    container: replace_env(&w.container, Some(e)),
+                    layout: w.layout.clone(),
+                    pane: w
+                        .pane
+                        .iter()
+                        .map(|p| Pane {
+                            command: replace_env(&p.command, Some(e)),
+                            dir: replace_env(p.dir.to_str().unwrap(), Some(e)).into(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Captured groups, which `$n` is replaced with
+pub trait Groups {
+    fn group(&self, n: usize) -> Option<String>;
 }
 
-fn get_var(name: &str, e: Option<&capturing_glob::Entry>) -> String {
+impl Groups for capturing_glob::Entry {
+    fn group(&self, n: usize) -> Option<String> {
+        let group = capturing_glob::Entry::group(self, n)?;
+        Some(group.to_str().unwrap_or_default().into())
+    }
+}
+
+impl Groups for Vec<String> {
+    fn group(&self, n: usize) -> Option<String> {
+        self.get(n).cloned()
+    }
+}
+
+fn get_var(name: &str, e: Option<&dyn Groups>) -> String {
     match str::parse::<usize>(name) {
-        Ok(n) => e.map_or(String::new(), |e| {
-            e.group(n)
-                .map_or(String::new(), |s| s.to_str().unwrap_or_default().into())
-        }),
+        Ok(n) => e.and_then(|e| e.group(n)).unwrap_or_default(),
         Err(_) => std::env::var(name).unwrap_or_default(),
     }
 }
 
-fn replace_env(p: &str, e: Option<&capturing_glob::Entry>) -> String {
+/// Returns project files matching import pattern
+pub fn import_paths(
+    pattern: &str,
+) -> anyhow::Result<impl Iterator<Item = anyhow::Result<PathBuf>>> {
+    let paths = capturing_glob::glob(&replace_env(pattern, None))?;
+    Ok(paths.map(|entry| Ok(entry?.path().to_path_buf())))
+}
+
+/// Escapes string, so that `replace_env` leaves it as is
+pub fn escape_env(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('$', "\\$")
+}
+
+/// Escapes root dir, so that after expansion glob matches only this dir
+pub fn escape_root(s: &str) -> String {
+    escape_env(&capturing_glob::Pattern::escape(s))
+}
+
+/// Single window, running given command
+pub fn default_windows(name: &str, command: &str) -> Vec<Window> {
+    vec![Window {
+        name: name.into(),
+        command: command.into(),
+        dir: PathBuf::new(),
+        env: BTreeMap::new(),
+        options: BTreeMap::new(),
+        kill: Vec::new(),
+        container: String::new(),
+        layout: String::new(),
+        pane: Vec::new(),
+    }]
+}
+
+/// Intervals must be positive, zero one would poll in busy loop
+fn positive(duration: &chrono::Duration) -> Result<(), serde_valid::validation::Error> {
+    match *duration > chrono::Duration::zero() {
+        true => Ok(()),
+        false => Err(serde_valid::validation::Error::Custom(
+            "must be positive".to_string(),
+        )),
+    }
+}
+
+fn positive_or_none(
+    duration: &Option<chrono::Duration>,
+) -> Result<(), serde_valid::validation::Error> {
+    duration.as_ref().map_or(Ok(()), positive)
+}
+
+/// Command without `{}` would open terminal, which does not attach anything
+fn placeholder(command: &str) -> Result<(), serde_valid::validation::Error> {
+    match command.is_empty() || command.contains("{}") {
+        true => Ok(()),
+        false => Err(serde_valid::validation::Error::Custom(
+            "must contain `{}`, which is replaced with attach command".to_string(),
+        )),
+    }
+}
+
+fn is_empty_path(p: &Path) -> bool {
+    p.as_os_str().is_empty()
+}
+
+fn replace_env_map(m: &BTreeMap<String, String>, e: &dyn Groups) -> BTreeMap<String, String> {
+    m.iter()
+        .map(|(k, v)| (k.clone(), replace_env(v, Some(e))))
+        .collect()
+}
+
+fn replace_env(p: &str, e: Option<&dyn Groups>) -> String {
     let mut prev = '\0';
     let mut res = String::new();
     let mut varname = String::new();
@@ -177,3 +752,33 @@ fn replace_env(p: &str, e: Option<&capturing_glob::Entry>) -> String {
 //         })
 //     }
 // }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config(toml: &str) -> anyhow::Result<Config> {
+        let toml = format!(
+            "[logger]\nlevel = 'info'\nlog_path = '/tmp/tiramisu.log'\n{}",
+            toml
+        );
+        let config: Config = toml::from_str(&toml)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_intervals() {
+        assert!(config("").unwrap().snapshot.interval.is_none());
+        let interval = config("[snapshot]\ninterval = 60")
+            .unwrap()
+            .snapshot
+            .interval;
+        assert_eq!(interval, Some(chrono::Duration::seconds(60)));
+        // zero interval would save snapshot in busy loop
+        let e = config("[snapshot]\ninterval = 0").unwrap_err();
+        assert!(e.to_string().contains("must be positive"), "{}", e);
+        assert!(config("[snapshot]\ninterval = -1").is_err());
+        assert!(config("[tmux]\nrefresh_interval = 0").is_err());
+    }
+}
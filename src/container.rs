@@ -0,0 +1,275 @@
+//! Windows running inside of docker containers
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, anyhow, bail};
+use serde::Deserialize;
+
+/// Running container, found by `container` option of window
+#[derive(Debug, Clone, PartialEq)]
+pub struct Container {
+    pub id: String,
+    /// Directory commands are run in, default one of container if not set
+    pub workdir: Option<PathBuf>,
+}
+
+/// Part of devcontainer.json, which is needed to find container
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Devcontainer {
+    workspace_folder: Option<PathBuf>,
+    docker_compose_file: Option<serde_json::Value>,
+    service: Option<String>,
+}
+
+/// Finds running container of session with given root
+pub fn resolve(container: &str, root: &Path) -> anyhow::Result<Container> {
+    lookup("docker", container, root)
+}
+
+/// Same as [resolve], but queries containers with given docker binary
+fn lookup(docker: &str, container: &str, root: &Path) -> anyhow::Result<Container> {
+    if container == "devcontainer" {
+        return devcontainer(docker, root);
+    }
+    if let Some(service) = container.strip_prefix("compose:") {
+        return Ok(Container {
+            id: find(docker, &compose_labels(root, service))?,
+            workdir: None,
+        });
+    }
+    Ok(Container {
+        id: container.to_string(),
+        workdir: None,
+    })
+}
+
+/// Wraps command, so that it runs in container, empty command starts container shell
+/// Relative `dir` is joined to container workdir
+pub fn wrap(
+    container: &Container,
+    command: &str,
+    dir: &Path,
+    env: &BTreeMap<String, String>,
+) -> String {
+    let mut args = vec!["docker".to_string(), "exec".into(), "-it".into()];
+    if let Some(workdir) = &container.workdir {
+        let workdir = match dir.as_os_str().is_empty() {
+            true => workdir.clone(),
+            false => workdir.join(dir),
+        };
+        args.extend(["-w".into(), workdir.to_string_lossy().into()]);
+    }
+    for (k, v) in env {
+        args.extend(["-e".into(), format!("{}={}", k, v)]);
+    }
+    let command = match command.is_empty() {
+        true => r#"exec "${SHELL:-/bin/sh}""#,
+        false => command,
+    };
+    args.extend([
+        container.id.clone(),
+        "sh".into(),
+        "-c".into(),
+        command.into(),
+    ]);
+    shell_words::join(args)
+}
+
+fn devcontainer(docker: &str, root: &Path) -> anyhow::Result<Container> {
+    let path = [".devcontainer/devcontainer.json", ".devcontainer.json"]
+        .iter()
+        .map(|p| root.join(p))
+        .find(|p| p.is_file())
+        .ok_or(anyhow!("no devcontainer.json in {}", root.display()))?;
+    let text = std::fs::read_to_string(&path)?;
+    let config: Devcontainer = serde_json::from_str(&strip_jsonc(&text))
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+
+    // compose files are relative to devcontainer.json, project is named after the first one
+    let compose = match &config.docker_compose_file {
+        Some(serde_json::Value::String(file)) => Some(file.clone()),
+        Some(serde_json::Value::Array(files)) => {
+            files.first().and_then(|f| f.as_str()).map(Into::into)
+        }
+        _ => None,
+    };
+    let (labels, workdir) = match (compose, &config.service) {
+        (Some(file), Some(service)) => {
+            let file = path.parent().unwrap_or(root).join(file);
+            let dir = file.parent().unwrap_or(root);
+            let dir = dir.canonicalize().unwrap_or(dir.to_path_buf());
+            (compose_labels(&dir, service), config.workspace_folder)
+        }
+        (Some(_), None) => bail!("{}: compose service is not set", path.display()),
+        (None, _) => {
+            let name = root.file_name().unwrap_or_default();
+            let workdir = config
+                .workspace_folder
+                .unwrap_or(Path::new("/workspaces").join(name));
+            let label = format!("devcontainer.local_folder={}", root.display());
+            (vec![label], Some(workdir))
+        }
+    };
+    Ok(Container {
+        id: find(docker, &labels)?,
+        workdir,
+    })
+}
+
+fn compose_labels(dir: &Path, service: &str) -> Vec<String> {
+    vec![
+        format!("com.docker.compose.project.working_dir={}", dir.display()),
+        format!("com.docker.compose.service={}", service),
+    ]
+}
+
+/// Returns id of running container with given labels
+fn find(docker: &str, labels: &[String]) -> anyhow::Result<String> {
+    let mut command = Command::new(docker);
+    command.args(["ps", "-q"]);
+    for label in labels {
+        command.args(["--filter", &format!("label={}", label)]);
+    }
+    let output = command.output().context("failed to run docker")?;
+    if !output.status.success() {
+        bail!(
+            "docker failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.lines().next() {
+        Some(id) if !id.is_empty() => Ok(id.to_string()),
+        _ => bail!("no running container with labels {}", labels.join(", ")),
+    }
+}
+
+/// Removes comments and trailing commas, which are allowed in devcontainer.json
+fn strip_jsonc(text: &str) -> String {
+    let mut res = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                res.push(c);
+                while let Some(c) = chars.next() {
+                    res.push(c);
+                    match c {
+                        '\\' => res.extend(chars.next()),
+                        '"' => break,
+                        _ => (),
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for c in chars.by_ref() {
+                    if (prev, c) == ('*', '/') {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '}' | ']' => {
+                let trimmed = res.trim_end().len();
+                if res[..trimmed].ends_with(',') {
+                    res.replace_range(trimmed - 1..trimmed, "");
+                }
+                res.push(c);
+            }
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_strip_jsonc() {
+        let text = r#"{
+            // comment with "quote"
+            "a": "// not a comment", /* block
+            comment */ "b": [1, 2,],
+            "c": "\"/*",
+        }"#;
+        let value: serde_json::Value = serde_json::from_str(&strip_jsonc(text)).unwrap();
+        assert_eq!(value["a"], "// not a comment");
+        assert_eq!(value["b"], serde_json::json!([1, 2]));
+        assert_eq!(value["c"], "\"/*");
+    }
+
+    #[test]
+    fn test_wrap() {
+        let container = Container {
+            id: "c1".into(),
+            workdir: Some("/workspaces/app".into()),
+        };
+        let env = BTreeMap::from([("A".to_string(), "1 2".to_string())]);
+        assert_eq!(
+            wrap(&container, "cargo test", Path::new("src"), &env),
+            "docker exec -it -w /workspaces/app/src -e 'A=1 2' c1 sh -c 'cargo test'"
+        );
+        let container = Container {
+            id: "db".into(),
+            workdir: None,
+        };
+        assert_eq!(
+            wrap(&container, "", Path::new(""), &BTreeMap::new()),
+            r#"docker exec -it db sh -c 'exec "${SHELL:-/bin/sh}"'"#
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let root = std::env::temp_dir().join(format!("tiramisu-container-{}", std::process::id()));
+        let project = root.join("app");
+        std::fs::create_dir_all(project.join(".devcontainer")).unwrap();
+        // echo instead of docker prints its arguments as container id
+        let resolve = |container| lookup("echo", container, &project);
+
+        assert_eq!(resolve("web").unwrap().id, "web");
+
+        let c = resolve("compose:db").unwrap();
+        let expected = format!(
+            "ps -q --filter label=com.docker.compose.project.working_dir={} \
+             --filter label=com.docker.compose.service=db",
+            project.display()
+        );
+        assert_eq!(c.id, expected);
+
+        assert!(resolve("devcontainer").is_err());
+        let config = project.join(".devcontainer/devcontainer.json");
+        std::fs::write(&config, "{\"image\": \"rust\", // dev\n}").unwrap();
+        let c = resolve("devcontainer").unwrap();
+        let expected = format!(
+            "ps -q --filter label=devcontainer.local_folder={}",
+            project.display()
+        );
+        assert_eq!(c.id, expected);
+        assert_eq!(c.workdir, Some("/workspaces/app".into()));
+
+        std::fs::write(
+            &config,
+            r#"{"dockerComposeFile": ["../compose.yml"], "service": "dev", "workspaceFolder": "/src"}"#,
+        )
+        .unwrap();
+        let c = resolve("devcontainer").unwrap();
+        assert!(c.id.contains(&format!(
+            "working_dir={} ",
+            project.canonicalize().unwrap().display()
+        )));
+        assert!(c.id.ends_with("service=dev"));
+        assert_eq!(c.workdir, Some("/src".into()));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
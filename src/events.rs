@@ -0,0 +1,110 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+/// How often input thread checks whether it should stop
+const INPUT_POLL: Duration = Duration::from_millis(100);
+
+/// Application event
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// Terminal input
+    Input(crossterm::event::Event),
+    /// Matcher has new results
+    Matcher,
+    /// New log message
+    Message,
+    /// Tmux state has changed
+    Refresh,
+    /// Source with given index is read, sessions are none if reading failed
+    Source(usize, Option<Vec<crate::config::Session>>),
+}
+
+/// Event queue, events are produced by input thread and by anyone holding sender
+pub struct Events {
+    tx: Sender<Event>,
+    rx: Receiver<Event>,
+    input: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl Events {
+    /// Creates queue and starts reading terminal input
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        let mut events = Self {
+            tx,
+            rx,
+            input: None,
+        };
+        events.start_input();
+        events
+    }
+
+    /// Returns callback, which sends given event
+    pub fn notifier(&self, event: Event) -> Arc<dyn Fn() + Sync + Send> {
+        let tx = self.tx.clone();
+        Arc::new(move || {
+            let _ = tx.send(event.clone());
+        })
+    }
+
+    /// Returns sender, which may be used from other threads
+    pub fn sender(&self) -> Sender<Event> {
+        self.tx.clone()
+    }
+
+    /// Waits for next event
+    pub fn next(&self, timeout: Duration) -> Option<Event> {
+        match self.rx.recv_timeout(timeout) {
+            Ok(e) => Some(e),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => panic!("event queue disconnected"),
+        }
+    }
+
+    /// Returns next event if it is already available
+    pub fn try_next(&self) -> Option<Event> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Starts reading terminal input in background
+    pub fn start_input(&mut self) {
+        if self.input.is_some() {
+            return;
+        }
+        let running = Arc::new(AtomicBool::new(true));
+        let (r, tx) = (running.clone(), self.tx.clone());
+        let handle = std::thread::spawn(move || {
+            while r.load(Ordering::Relaxed) {
+                match crossterm::event::poll(INPUT_POLL) {
+                    Ok(true) => match crossterm::event::read() {
+                        Ok(e) => {
+                            let _ = tx.send(Event::Input(e));
+                        }
+                        Err(e) => log::error!("Failed to read input: {}", e),
+                    },
+                    Ok(false) => (),
+                    Err(e) => {
+                        log::error!("Failed to poll input: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        self.input = Some((running, handle));
+    }
+
+    /// Stops reading terminal input, so that other process may use terminal
+    pub fn stop_input(&mut self) {
+        if let Some((running, handle)) = self.input.take() {
+            running.store(false, Ordering::Relaxed);
+            let _ = handle.join();
+        }
+    }
+}
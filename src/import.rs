@@ -0,0 +1,510 @@
+//! Reads tmuxinator and tmuxp project files as sessions
+
+use std::path::Path;
+
+use anyhow::{anyhow, bail};
+use serde::Serialize;
+use serde_norway::{Mapping, Value};
+
+use crate::config::{self, escape_env};
+
+/// Converted session and descriptions of fields, which were not converted
+pub struct Imported {
+    pub session: config::Session,
+    pub warnings: Vec<String>,
+}
+
+/// Reads project file, format is detected by its fields
+/// JSON files are read too, as JSON is subset of YAML
+pub fn read(path: &Path) -> anyhow::Result<Imported> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+fn parse(text: &str) -> anyhow::Result<Imported> {
+    let value: Value = serde_norway::from_str(text)?;
+    let Value::Mapping(file) = value else {
+        bail!("project file is not a mapping");
+    };
+    let mut importer = Importer::default();
+    let session = match file.contains_key("session_name") {
+        true => importer.tmuxp(&file)?,
+        false => importer.tmuxinator(&file)?,
+    };
+    Ok(Imported {
+        session,
+        warnings: importer.warnings,
+    })
+}
+
+/// Formats sessions as `[[session]]` entries of config
+pub fn to_toml(sessions: &[config::Session]) -> anyhow::Result<String> {
+    #[derive(Serialize)]
+    struct Document<'a> {
+        session: &'a [config::Session],
+    }
+    Ok(toml::to_string(&Document { session: sessions })?)
+}
+
+#[derive(Default)]
+struct Importer {
+    warnings: Vec<String>,
+}
+
+/// Pane being converted
+struct Pane {
+    commands: Vec<String>,
+    dir: String,
+}
+
+impl Importer {
+    fn tmuxinator(&mut self, file: &Mapping) -> anyhow::Result<config::Session> {
+        let mut session = new_session(
+            first(file, &["name", "project_name"]).ok_or(anyhow!("no project name"))?,
+            first(file, &["root", "project_root"]).unwrap_or("~".into()),
+        );
+        let pre_window = commands(get(file, &["pre_window", "pre_tab"]));
+        for command in commands(get(
+            file,
+            &["on_project_first_start", "on_project_start", "pre"],
+        )) {
+            session.tmux_commands.push(run_shell(&command));
+        }
+
+        let windows = get(file, &["windows", "tabs"]).and_then(Value::as_sequence);
+        for (i, window) in windows.into_iter().flatten().enumerate() {
+            let Some((name, window)) = window.as_mapping().and_then(|m| m.iter().next()) else {
+                self.warn(format!("window {} is not a mapping", i));
+                continue;
+            };
+            let name = string(name).unwrap_or(i.to_string());
+            let mut pre = pre_window.clone();
+            let mut dir = String::new();
+            let mut layout = String::new();
+            let mut options = Vec::new();
+            let panes = match window {
+                Value::Mapping(window) => {
+                    for (key, value) in window {
+                        match key.as_str().unwrap_or_default() {
+                            "root" => dir = string(value).unwrap_or_default(),
+                            "layout" => layout = string(value).unwrap_or_default(),
+                            "pre" => pre.extend(commands(Some(value))),
+                            "synchronize" if value.as_bool() != Some(false) => {
+                                options.push(("synchronize-panes", "on"))
+                            }
+                            "panes" | "synchronize" => (),
+                            key => self.warn(format!("window {}: unsupported field {}", name, key)),
+                        }
+                    }
+                    let panes = window.get("panes").and_then(Value::as_sequence);
+                    panes
+                        .into_iter()
+                        .flatten()
+                        .map(|pane| Pane {
+                            // named panes are maps from name to commands
+                            commands: match pane {
+                                Value::Mapping(m) => commands(m.values().next()),
+                                pane => commands(Some(pane)),
+                            },
+                            dir: String::new(),
+                        })
+                        .collect()
+                }
+                window => vec![Pane {
+                    commands: commands(Some(window)),
+                    dir: String::new(),
+                }],
+            };
+
+            let mut window = new_window(name, dir, panes, &pre);
+            window.layout = layout;
+            window.options = options
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            session.window.push(window);
+        }
+
+        if let Some(window) = string_of(file, "startup_window") {
+            session.focus = Some(config::Focus {
+                window: escape_env(&window),
+                pane: file
+                    .get("startup_pane")
+                    .and_then(Value::as_u64)
+                    .map(|p| p as usize),
+                always: false,
+            });
+        }
+        self.unsupported(
+            file,
+            &[
+                "name",
+                "project_name",
+                "root",
+                "project_root",
+                "windows",
+                "tabs",
+                "pre_window",
+                "pre_tab",
+                "on_project_first_start",
+                "on_project_start",
+                "pre",
+                "startup_window",
+                "startup_pane",
+            ],
+        );
+        self.finish(session)
+    }
+
+    fn tmuxp(&mut self, file: &Mapping) -> anyhow::Result<config::Session> {
+        let mut session = new_session(
+            string_of(file, "session_name").ok_or(anyhow!("no session name"))?,
+            string_of(file, "start_directory").unwrap_or("~".into()),
+        );
+        session.env = map(file.get("environment"));
+        session.options = map(file.get("options"));
+        let pre_window = commands(file.get("shell_command_before"));
+        for command in commands(file.get("before_script")) {
+            session.tmux_commands.push(run_shell(&command));
+        }
+
+        let windows = file.get("windows").and_then(Value::as_sequence);
+        for (i, window) in windows.into_iter().flatten().enumerate() {
+            let Some(window) = window.as_mapping() else {
+                self.warn(format!("window {} is not a mapping", i));
+                continue;
+            };
+            let name = string_of(window, "window_name").unwrap_or(i.to_string());
+            let mut pre = pre_window.clone();
+            pre.extend(commands(window.get("shell_command_before")));
+
+            let mut focus = None;
+            let panes = window.get("panes").and_then(Value::as_sequence);
+            let panes = panes.into_iter().flatten().enumerate().map(|(j, pane)| {
+                let Value::Mapping(pane) = pane else {
+                    // `blank` and `pane` are shortcuts for empty pane
+                    let commands = match pane.as_str() {
+                        Some("blank" | "pane") => Vec::new(),
+                        _ => commands(Some(pane)),
+                    };
+                    return Pane {
+                        commands,
+                        dir: String::new(),
+                    };
+                };
+                if pane.get("focus").and_then(Value::as_bool) == Some(true) {
+                    focus = Some(j);
+                }
+                for key in pane.keys().filter_map(Value::as_str) {
+                    if !["shell_command", "start_directory", "focus"].contains(&key) {
+                        self.warn(format!("pane {}.{}: unsupported field {}", name, j, key));
+                    }
+                }
+                Pane {
+                    commands: commands(pane.get("shell_command")),
+                    dir: string_of(pane, "start_directory").unwrap_or_default(),
+                }
+            });
+            let panes: Vec<_> = panes.collect();
+
+            let dir = string_of(window, "start_directory").unwrap_or_default();
+            let mut w = new_window(name, dir, panes, &pre);
+            w.layout = string_of(window, "layout").unwrap_or_default();
+            w.options = map(window.get("options"));
+            w.env = map(window.get("environment"));
+            if window.get("focus").and_then(Value::as_bool) == Some(true) || focus.is_some() {
+                session.focus = Some(config::Focus {
+                    window: w.name.clone(),
+                    pane: focus,
+                    always: false,
+                });
+            }
+            for key in window.keys().filter_map(Value::as_str) {
+                let supported = [
+                    "window_name",
+                    "start_directory",
+                    "shell_command_before",
+                    "panes",
+                    "layout",
+                    "options",
+                    "environment",
+                    "focus",
+                ];
+                if !supported.contains(&key) {
+                    self.warn(format!("window {}: unsupported field {}", w.name, key));
+                }
+            }
+            session.window.push(w);
+        }
+
+        self.unsupported(
+            file,
+            &[
+                "session_name",
+                "start_directory",
+                "environment",
+                "options",
+                "shell_command_before",
+                "before_script",
+                "windows",
+            ],
+        );
+        self.finish(session)
+    }
+
+    fn unsupported(&mut self, file: &Mapping, supported: &[&str]) {
+        for key in file.keys().filter_map(Value::as_str) {
+            if !supported.contains(&key) {
+                self.warn(format!("unsupported field {}", key));
+            }
+        }
+    }
+
+    fn finish(&mut self, session: config::Session) -> anyhow::Result<config::Session> {
+        if session.window.is_empty() {
+            bail!("project has no windows");
+        }
+        Ok(session)
+    }
+
+    fn warn(&mut self, warning: String) {
+        self.warnings.push(warning);
+    }
+}
+
+/// Creates window from panes, `pre` commands are run in each pane before its own ones
+fn new_window(name: String, dir: String, panes: Vec<Pane>, pre: &[String]) -> config::Window {
+    let mut panes = panes.into_iter().map(|pane| {
+        // pane without commands is left with shell
+        let command = match pane.commands.is_empty() {
+            true => String::new(),
+            false => pre
+                .iter()
+                .chain(pane.commands.iter())
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("; "),
+        };
+        (escape_env(&command), path(&pane.dir))
+    });
+    let (command, first_dir) = panes.next().unwrap_or_default();
+    let pane = panes
+        .map(|(command, dir)| config::Pane {
+            command,
+            dir: dir.into(),
+        })
+        .collect();
+    // first pane is window itself
+    let dir = Path::new(&path(&dir)).join(first_dir);
+
+    config::Window {
+        name: escape_env(&name),
+        command,
+        dir,
+        env: Default::default(),
+        options: Default::default(),
+        kill: Vec::new(),
+        container: String::new(),
+        layout: String::new(),
+        pane,
+    }
+}
+
+fn new_session(name: String, root: String) -> config::Session {
+    config::Session {
+        root: home(&root, config::escape_root).into(),
+        name: escape_env(&name),
+        tags: Vec::new(),
+        description: String::new(),
+        server: Default::default(),
+        env: Default::default(),
+        options: Default::default(),
+        tmux_commands: Vec::new(),
+        view: false,
+        focus: None,
+        window: Vec::new(),
+    }
+}
+
+/// Escapes path, `~` is replaced with `$HOME` instead
+fn path(path: &str) -> String {
+    home(path, escape_env)
+}
+
+/// Escapes path with given function, leading `~` becomes unescaped `$HOME`
+fn home(path: &str, escape: fn(&str) -> String) -> String {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            "$HOME".to_string() + &escape(rest)
+        }
+        _ => escape(path),
+    }
+}
+
+/// tmux command, which runs shell command in background
+fn run_shell(command: &str) -> String {
+    format!("run-shell -b {}", shell_words::quote(&escape_env(command)))
+}
+
+fn get<'a>(file: &'a Mapping, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter().find_map(|k| file.get(*k))
+}
+
+fn first(file: &Mapping, keys: &[&str]) -> Option<String> {
+    get(file, keys).and_then(string)
+}
+
+fn string_of(file: &Mapping, key: &str) -> Option<String> {
+    file.get(key).and_then(string)
+}
+
+fn string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Reads single command or list of commands
+fn commands(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Sequence(s)) => s
+            .iter()
+            .flat_map(|v| match v {
+                // tmuxp allows `{cmd: ...}` items
+                Value::Mapping(m) => m.get("cmd").and_then(string),
+                v => string(v),
+            })
+            .collect(),
+        Some(v) => string(v).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+fn map(value: Option<&Value>) -> std::collections::BTreeMap<String, String> {
+    let Some(Value::Mapping(m)) = value else {
+        return Default::default();
+    };
+    m.iter()
+        .filter_map(|(k, v)| Some((string(k)?, escape_env(&string(v)?))))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_tmuxinator() {
+        let imported = parse(
+            "
+name: app
+root: ~/src/app
+pre_window: nvm use
+on_project_start: make deps
+startup_window: logs
+attach: false
+windows:
+  - editor: vim
+  - server:
+      root: server
+      layout: main-vertical
+      synchronize: true
+      panes:
+        - cargo run
+        - logs: [cd log, tail -f $LOG]
+        -
+  - logs: journalctl -f
+",
+        )
+        .unwrap();
+        let s = imported.session;
+        assert_eq!(s.name, "app");
+        assert_eq!(s.root, PathBuf::from("$HOME/src/app"));
+        assert_eq!(s.tmux_commands, ["run-shell -b 'make deps'"]);
+        assert_eq!(s.window.len(), 3);
+        assert_eq!(s.window[0].name, "editor");
+        assert_eq!(s.window[0].command, "nvm use; vim");
+        let w = &s.window[1];
+        assert_eq!(w.dir, PathBuf::from("server"));
+        assert_eq!(w.layout, "main-vertical");
+        assert_eq!(w.options["synchronize-panes"], "on");
+        assert_eq!(w.command, "nvm use; cargo run");
+        assert_eq!(w.pane[0].command, "nvm use; cd log; tail -f \\$LOG");
+        assert_eq!(w.pane[1].command, "");
+        assert_eq!(s.focus.unwrap().window, "logs");
+        assert_eq!(imported.warnings, ["unsupported field attach"]);
+    }
+
+    #[test]
+    fn test_tmuxp() {
+        let imported = parse(
+            "
+session_name: api
+start_directory: /srv/api
+environment: {MODE: dev}
+windows:
+  - window_name: code
+    layout: tiled
+    shell_command_before: source .env
+    panes:
+      - vim
+      - shell_command: [{cmd: pytest}]
+        start_directory: tests
+        focus: true
+      - blank
+  - window_name: db
+    start_directory: db
+    window_index: 5
+",
+        )
+        .unwrap();
+        let s = imported.session;
+        assert_eq!(s.name, "api");
+        assert_eq!(s.root, PathBuf::from("/srv/api"));
+        assert_eq!(s.env["MODE"], "dev");
+        let w = &s.window[0];
+        assert_eq!(w.layout, "tiled");
+        assert_eq!(w.command, "source .env; vim");
+        assert_eq!(w.pane[0].command, "source .env; pytest");
+        assert_eq!(w.pane[0].dir, PathBuf::from("tests"));
+        assert_eq!(w.pane[1].command, "");
+        assert_eq!(s.window[1].dir, PathBuf::from("db"));
+        let focus = s.focus.unwrap();
+        assert_eq!((focus.window.as_str(), focus.pane), ("code", Some(1)));
+        assert_eq!(
+            imported.warnings,
+            ["window db: unsupported field window_index"]
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse("- a").is_err());
+        assert!(parse("windows: [a: b]").is_err());
+        assert!(parse("name: app").is_err());
+        assert!(parse("name: app\nwindows: [1]").is_err());
+    }
+
+    #[test]
+    fn test_to_toml() {
+        let session = parse("name: app\nwindows: [a: b]").unwrap().session;
+        let text = to_toml(&[session]).unwrap();
+        let config: crate::snapshot::Snapshot = toml::from_str(&text).unwrap();
+        assert_eq!(config.session[0].window[0].command, "b");
+
+        // root is expanded as glob, but matches only itself
+        let root = std::env::temp_dir().join(format!("tiramisu-import-{}", std::process::id()));
+        let dir = root.join("$x [a]*?");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(root.join("$x ab")).unwrap();
+        let yaml = format!("name: app\nroot: '{}'\nwindows: [a: b]", dir.display());
+        let session = parse(&yaml).unwrap().session;
+        let expanded: Vec<_> = session.expand().unwrap().collect();
+        assert_eq!(expanded.len(), 1);
+        assert_eq!(expanded[0].as_ref().unwrap().root, dir);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,50 +1,61 @@
 use std::{
-    collections::VecDeque,
-    fs::OpenOptions,
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
 };
 
 use fern::Dispatch;
 
 use crate::{config, ui};
 
+/// How often log file is checked for new lines, when it is followed
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Logger
-/// Logs are stored in file and localy, so that they can appear in popups
+/// Logs are stored in file and localy, so that they can appear in popups and log view
+/// Local history is kept for the whole session
 pub struct Logger {
-    logs: Arc<Mutex<VecDeque<ui::Message>>>,
+    logs: Arc<Mutex<Vec<ui::Message>>>,
+    /// Number of messages, which popups has expired
+    expired: usize,
     ttl: chrono::Duration,
 }
 
 impl Logger {
-    /// Creates new logger
-    pub fn new(config: &config::Logger) -> std::io::Result<Self> {
+    /// Creates new logger, `notify` is called on every new message
+    pub fn new(
+        config: &config::Logger,
+        notify: Arc<dyn Fn() + Sync + Send>,
+    ) -> std::io::Result<Self> {
         let logger = Self {
-            logs: Arc::new(Mutex::new(VecDeque::new())),
+            logs: Arc::new(Mutex::new(Vec::new())),
+            expired: 0,
             ttl: config.message_ttl,
         };
         let sender = logger.logs.clone();
 
         std::fs::create_dir_all(config.log_path.parent().unwrap())?;
+        let format = config.format;
+        let file: Box<dyn Write + Send> = Box::new(RotatingFile::open(config)?);
         Dispatch::new()
             // log to file
             .chain({
                 Dispatch::new()
-                    .format(|out, message, record| {
-                        out.finish(format_args!(
+                    .format(move |out, message, record| match format {
+                        config::LogFormat::Text => out.finish(format_args!(
                             "{} [{}] {}",
                             chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
                             record.level(),
                             message
-                        ))
+                        )),
+                        config::LogFormat::Json => {
+                            out.finish(format_args!("{}", json(record, &message.to_string())))
+                        }
                     })
                     .level(config.level)
-                    .chain(
-                        OpenOptions::new()
-                            .write(true)
-                            .create(true)
-                            .append(true)
-                            .open(&config.log_path)?,
-                    )
+                    .chain(fern::Output::writer(file, "\n"))
             })
             // log to local queue
             .chain({
@@ -52,11 +63,12 @@ impl Logger {
                     .level(config.level)
                     .format(|out, message, _| out.finish(format_args!("{}", message)))
                     .chain(fern::Output::call(move |record| {
-                        sender.lock().unwrap().push_back(ui::Message::new(
+                        sender.lock().unwrap().push(ui::Message::new(
                             record.level(),
                             record.args().to_string(),
                             chrono::Local::now(),
                         ));
+                        notify();
                     }))
             })
             .apply()
@@ -68,11 +80,427 @@ impl Logger {
     /// List all messages, that hasn't expired yet
     pub fn messages(&mut self) -> Vec<ui::Message> {
         let now = chrono::Local::now();
-        let mut logs = self.logs.lock().unwrap();
-        while logs.front().is_some_and(|m| now - m.time() >= self.ttl) {
-            logs.pop_front();
+        let logs = self.logs.lock().unwrap();
+        while logs
+            .get(self.expired)
+            .is_some_and(|m| now - m.time() >= self.ttl)
+        {
+            self.expired += 1;
+        }
+
+        logs[self.expired..].to_vec()
+    }
+
+    /// Passes all messages logged since start to `f`, which must not log anything,
+    /// since messages are locked meanwhile
+    pub fn history<R>(&self, f: impl FnOnce(&[ui::Message]) -> R) -> R {
+        f(&self.logs.lock().unwrap())
+    }
+
+    /// Returns total number of messages ever logged
+    pub fn count(&self) -> usize {
+        self.logs.lock().unwrap().len()
+    }
+
+    /// Returns time, when the oldest message expires
+    pub fn next_expiry(&self) -> Option<chrono::DateTime<chrono::Local>> {
+        self.logs
+            .lock()
+            .unwrap()
+            .get(self.expired)
+            .map(|m| m.time() + self.ttl)
+    }
+}
+
+/// Log file, which is rotated once it grows too big or too old
+/// Rotated files are renamed to `<path>.1`, `<path>.2`..., the oldest ones are removed
+/// Several processes may write to the same file, each of them follows rotations made by others,
+/// though rotated file may be lost if two of them rotate at the same moment
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    created: SystemTime,
+    max_size: Option<u64>,
+    max_age: Option<chrono::Duration>,
+    keep: usize,
+    /// Whether last write finished line, records are written in parts and are never split
+    line_start: bool,
+}
+
+impl RotatingFile {
+    fn open(config: &config::Logger) -> std::io::Result<Self> {
+        let file = Self::append(&config.log_path)?;
+        let metadata = file.metadata()?;
+        Ok(Self {
+            path: config.log_path.clone(),
+            size: metadata.len(),
+            created: metadata.created().unwrap_or(SystemTime::now()),
+            file,
+            max_size: config.max_size,
+            max_age: config.max_age,
+            keep: config.keep,
+            line_start: true,
+        })
+    }
+
+    fn append(path: &Path) -> std::io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    /// Checks whether `path` still refers to opened file, other process may have rotated it
+    fn is_current(&self) -> bool {
+        match (std::fs::metadata(&self.path), self.file.metadata()) {
+            (Ok(path), Ok(file)) => same_file(&path, &file),
+            _ => false,
+        }
+    }
+
+    /// Catches up with other processes: reopens rotated file and counts lines they appended
+    fn sync(&mut self) -> std::io::Result<()> {
+        if !self.is_current() {
+            self.file = Self::append(&self.path)?;
+            self.created = self.file.metadata()?.created().unwrap_or(SystemTime::now());
+        }
+        self.size = self.file.metadata()?.len();
+        Ok(())
+    }
+
+    fn due(&self) -> bool {
+        let age = SystemTime::now()
+            .duration_since(self.created)
+            .unwrap_or_default();
+        self.max_size.is_some_and(|max| self.size >= max)
+            || self
+                .max_age
+                .is_some_and(|max| chrono::Duration::from_std(age).is_ok_and(|age| age >= max))
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        for i in (1..self.keep).rev() {
+            match std::fs::rename(rotated(&self.path, i), rotated(&self.path, i + 1)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                _ => (),
+            }
+        }
+        match self.keep {
+            0 => std::fs::remove_file(&self.path)?,
+            _ => std::fs::rename(&self.path, rotated(&self.path, 1))?,
+        }
+        self.file = Self::append(&self.path)?;
+        self.size = 0;
+        self.created = SystemTime::now();
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.line_start {
+            self.sync()?;
+            if self.size > 0 && self.due() {
+                self.rotate()?;
+            }
+        }
+        let n = self.file.write(buf)?;
+        self.size += n as u64;
+        self.line_start = buf[..n].ends_with(b"\n");
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(unix)]
+fn same_file(a: &std::fs::Metadata, b: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+/// Without inodes, rotation is noticed only once file sizes differ
+#[cfg(not(unix))]
+fn same_file(a: &std::fs::Metadata, b: &std::fs::Metadata) -> bool {
+    a.len() == b.len()
+}
+
+/// Path of rotated log file, the bigger `i`, the older the file
+fn rotated(path: &Path, i: usize) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(format!(".{}", i));
+    path.into()
+}
+
+/// Formats record as JSON object, key-values of record are stored in `fields`
+fn json(record: &log::Record, message: &str) -> String {
+    struct Fields(serde_json::Map<String, serde_json::Value>);
+
+    impl<'kvs> log::kv::VisitSource<'kvs> for Fields {
+        fn visit_pair(
+            &mut self,
+            key: log::kv::Key<'kvs>,
+            value: log::kv::Value<'kvs>,
+        ) -> Result<(), log::kv::Error> {
+            let value = match (
+                value.to_u64(),
+                value.to_i64(),
+                value.to_f64(),
+                value.to_bool(),
+            ) {
+                (Some(v), _, _, _) => v.into(),
+                (_, Some(v), _, _) => v.into(),
+                (_, _, Some(v), _) => v.into(),
+                (_, _, _, Some(v)) => v.into(),
+                _ => value.to_string().into(),
+            };
+            self.0.insert(key.to_string(), value);
+            Ok(())
+        }
+    }
+
+    let mut fields = Fields(serde_json::Map::new());
+    // visitor never fails
+    let _ = record.key_values().visit(&mut fields);
+    serde_json::json!({
+        "time": chrono::Local::now().to_rfc3339(),
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "module": record.module_path(),
+        "message": message,
+        "fields": fields.0,
+    })
+    .to_string()
+}
+
+/// Prints log file to `out`, optionally only last `tail` lines, which pass `level`
+/// With `follow`, waits for new lines until interrupted or `out` is closed
+pub fn print(
+    path: &Path,
+    tail: Option<usize>,
+    follow: bool,
+    level: log::LevelFilter,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    let mut file = std::fs::File::open(path)?;
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    let lines = filter(&text, level);
+    let skip = lines.len().saturating_sub(tail.unwrap_or(usize::MAX));
+    for line in &lines[skip..] {
+        writeln!(out, "{}", line)?;
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    let mut offset = text.len() as u64;
+    // partial line is kept until it is finished
+    let mut pending = String::new();
+    loop {
+        std::thread::sleep(FOLLOW_INTERVAL);
+        let len = std::fs::metadata(path)?.len();
+        // file was truncated or replaced
+        if len < offset {
+            file = std::fs::File::open(path)?;
+            offset = 0;
+            pending.clear();
         }
+        file.seek(SeekFrom::Start(offset))?;
+        let mut bytes = Vec::new();
+        offset += file.read_to_end(&mut bytes)? as u64;
+        pending += &String::from_utf8_lossy(&bytes);
+        let Some(end) = pending.rfind('\n') else {
+            continue;
+        };
+        for line in filter(&pending[..end], level) {
+            writeln!(out, "{}", line)?;
+        }
+        pending.replace_range(..=end, "");
+    }
+}
+
+/// Returns lines of log file, which pass `level`
+/// Lines of multiline messages share level of their first line
+fn filter(text: &str, level: log::LevelFilter) -> Vec<&str> {
+    let mut passed = true;
+    text.lines()
+        .filter(|line| {
+            if let Some(l) = line_level(line) {
+                passed = l <= level;
+            }
+            passed
+        })
+        .collect()
+}
+
+/// Parses level of line formatted as `2024-01-01 00:00:00 [INFO] message` or JSON object
+fn line_level(line: &str) -> Option<log::Level> {
+    if line.starts_with('{') {
+        let value: serde_json::Value = serde_json::from_str(line).ok()?;
+        return value["level"].as_str()?.parse().ok();
+    }
+    let rest = line.get(20..)?.strip_prefix('[')?;
+    rest.split_once(']')?.0.parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        let text = "2024-01-01 00:00:00 [INFO] started\n\
+                    2024-01-01 00:00:01 [ERROR] failed:\n\
+                    second line\n\
+                    2024-01-01 00:00:02 [DEBUG] tmux [\"ls\"]\n\
+                    garbage";
+        assert_eq!(
+            line_level("2024-01-01 00:00:00 [WARN] x"),
+            Some(log::Level::Warn)
+        );
+        assert_eq!(line_level("second line"), None);
+        assert_eq!(
+            filter(text, log::LevelFilter::Warn),
+            ["2024-01-01 00:00:01 [ERROR] failed:", "second line"]
+        );
+        assert_eq!(filter(text, log::LevelFilter::Trace).len(), 5);
+        assert!(filter(text, log::LevelFilter::Off).is_empty());
+    }
+
+    /// Collects printed lines, appends `appended` to log once `tail` of them are printed
+    /// and fails as closed output on the next line
+    struct Output {
+        path: PathBuf,
+        text: String,
+        tail: usize,
+        appended: &'static str,
+    }
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.text += std::str::from_utf8(buf).unwrap();
+            let lines = self.text.lines().count();
+            if !self.text.ends_with('\n') || lines < self.tail {
+                return Ok(buf.len());
+            }
+            if lines > self.tail {
+                return Err(std::io::ErrorKind::BrokenPipe.into());
+            }
+            RotatingFile::append(&self.path)?.write_all(self.appended.as_bytes())?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_print() {
+        let path = std::env::temp_dir().join(format!("tiramisu-print-{}.log", std::process::id()));
+        let text = "2024-01-01 00:00:00 [INFO] first\n\
+                    2024-01-01 00:00:01 [DEBUG] second\n\
+                    2024-01-01 00:00:02 [WARN] third\n\
+                    2024-01-01 00:00:03 [ERROR] fourth\n";
+        std::fs::write(&path, text).unwrap();
+        let appended = "2024-01-01 00:00:04 [DEBUG] hidden\n\
+                        2024-01-01 00:00:05 [INFO] fifth\n";
+        let mut out = Output {
+            path: path.clone(),
+            text: String::new(),
+            tail: usize::MAX,
+            appended,
+        };
+
+        print(&path, Some(2), false, log::LevelFilter::Info, &mut out).unwrap();
+        assert_eq!(
+            out.text,
+            "2024-01-01 00:00:02 [WARN] third\n2024-01-01 00:00:03 [ERROR] fourth\n"
+        );
+
+        // followed lines are filtered as well, printing stops once output is closed
+        out.text.clear();
+        out.tail = 1;
+        assert!(print(&path, Some(1), true, log::LevelFilter::Info, &mut out).is_err());
+        assert_eq!(
+            out.text,
+            "2024-01-01 00:00:03 [ERROR] fourth\n2024-01-01 00:00:05 [INFO] fifth\n"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_rotation() {
+        let root = std::env::temp_dir().join(format!("tiramisu-log-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let path = root.join("tiramisu.log");
+        let toml = format!(
+            "level = 'info'\nlog_path = '{}'\nmax_size = 10\nkeep = 2",
+            path.display()
+        );
+        let config: config::Logger = toml::from_str(&toml).unwrap();
+        assert_eq!(config.format, config::LogFormat::Text);
+
+        let mut file = RotatingFile::open(&config).unwrap();
+        for line in [
+            "first line\n",
+            "second ",
+            "line\n",
+            "third line\n",
+            "fourth\n",
+        ] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        let read = |i| std::fs::read_to_string(rotated(&path, i)).unwrap_or_default();
+        // lines are never split, the oldest one is removed
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(read(1), "third line\n");
+        assert_eq!(read(2), "second line\n");
+        assert!(!rotated(&path, 3).exists());
+
+        // processes writing to the same file follow rotations made by each other
+        std::fs::remove_file(&path).unwrap();
+        let mut files = [
+            RotatingFile::open(&config).unwrap(),
+            RotatingFile::open(&config).unwrap(),
+        ];
+        for (i, line) in [
+            (0, "first line\n"),
+            (1, "second\n"),
+            (0, "third\n"),
+            (1, "fourth\n"),
+            (0, "fifth\n"),
+        ] {
+            files[i].write_all(line.as_bytes()).unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fourth\nfifth\n");
+        assert_eq!(read(1), "second\nthird\n");
+        assert_eq!(read(2), "first line\n");
+        std::fs::remove_dir_all(root).unwrap();
+    }
 
-        logs.iter().cloned().collect()
+    #[test]
+    fn test_json() {
+        let kvs = [
+            ("session", log::kv::Value::from("a b")),
+            ("ms", 12u64.into()),
+        ];
+        let record = log::Record::builder()
+            .level(log::Level::Debug)
+            .target("tiramisu::tmux")
+            .module_path(Some("tiramisu::tmux"))
+            .key_values(&kvs)
+            .build();
+        let line = json(&record, "tmux took 12ms");
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["level"], "DEBUG");
+        assert_eq!(value["module"], "tiramisu::tmux");
+        assert_eq!(value["message"], "tmux took 12ms");
+        assert_eq!(
+            value["fields"],
+            serde_json::json!({"session": "a b", "ms": 12})
+        );
+        assert_eq!(line_level(&line), Some(log::Level::Debug));
     }
 }
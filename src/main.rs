@@ -5,8 +5,13 @@ use clap::Parser;
 
 mod application;
 mod config;
+mod container;
+mod events;
+mod import;
 mod logger;
 mod paths;
+mod snapshot;
+mod source;
 mod tmux;
 mod ui;
 
@@ -20,6 +25,54 @@ struct Args {
     /// Print logs and quit
     #[arg(long)]
     logs: bool,
+
+    /// Print only last N lines of logs
+    #[arg(long, value_name = "N", requires = "logs")]
+    tail: Option<usize>,
+
+    /// Keep printing logs as they are written
+    #[arg(long, requires = "logs")]
+    follow: bool,
+
+    /// Print only logs of given level or more severe (error warn info debug trace)
+    #[arg(long, requires = "logs", default_value = "trace")]
+    level: log::LevelFilter,
+
+    /// Open session by name without picker
+    #[arg(long, value_name = "SESSION")]
+    open: Option<String>,
+
+    /// Window to focus in opened session (name or index)
+    #[arg(long, requires = "open")]
+    window: Option<String>,
+
+    /// Detach other clients of opened session
+    #[arg(long, requires = "open")]
+    detach: bool,
+
+    /// Attach opened session read-only
+    #[arg(long, requires = "open", conflicts_with = "detach")]
+    read_only: bool,
+
+    /// Save snapshot of running sessions and quit
+    #[arg(long)]
+    save: bool,
+
+    /// Recreate sessions from snapshot and quit
+    #[arg(long, conflicts_with = "save")]
+    restore: bool,
+
+    /// Print running session as config entry and quit
+    #[arg(long, value_name = "SESSION", conflicts_with_all = ["open", "save", "restore"])]
+    export: Option<String>,
+
+    /// Print tmuxinator or tmuxp project files as config entries and quit
+    #[arg(long, value_name = "FILE", num_args = 1..)]
+    import: Vec<PathBuf>,
+
+    /// Append exported session to file instead of printing it
+    #[arg(long, value_name = "FILE", requires = "export")]
+    output: Option<PathBuf>,
 }
 
 pub fn main() {
@@ -27,10 +80,35 @@ pub fn main() {
     let config = Config::new(args.config.unwrap_or(paths::config())).unwrap();
 
     if args.logs {
-        print!(
-            "{}",
-            std::fs::read_to_string(config.logger.log_path).unwrap()
-        );
+        let path = config.logger.log_path;
+        let mut out = std::io::stdout();
+        if let Err(e) = logger::print(&path, args.tail, args.follow, args.level, &mut out) {
+            eprintln!("Failed to print {}: {:#}", path.display(), e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // actions, which do not need picker
+    let result = match (args.open, args.export, args.save, args.restore) {
+        (Some(name), _, _, _) => Some(application::open(
+            &config,
+            &name,
+            args.window,
+            args.detach,
+            args.read_only,
+        )),
+        (_, Some(name), _, _) => Some(application::export(&config, &name, args.output.as_deref())),
+        _ if !args.import.is_empty() => Some(application::convert(&config, &args.import)),
+        (_, _, true, _) => Some(application::save(&config)),
+        (_, _, _, true) => Some(application::restore(&config)),
+        _ => None,
+    };
+    if let Some(result) = result {
+        if let Err(e) = result {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
         return;
     }
 
@@ -40,14 +118,13 @@ pub fn main() {
         app.render();
         app.update();
 
-        match app.selected() {
-            Some((name, session)) => {
-                if tmux::open(&name.to_string(), &session) {
-                    log::trace!("Exiting...");
-                    break;
-                }
+        if let Some((name, session, mode)) = app.selected() {
+            app.suspend();
+            if tmux::open(&name.to_string(), &session, &mode) {
+                log::trace!("Exiting...");
+                break;
             }
-            None => (),
+            app.resume();
         }
     }
 
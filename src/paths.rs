@@ -19,3 +19,21 @@ pub fn logs() -> PathBuf {
         .join("tiramisu")
         .join("tiramisu.log")
 }
+
+/// Default path to snapshot of running sessions
+/// Windows: %LOCALAPPDATA%\tiramisu\snapshot.toml
+/// Unix: $XDG_CACHE_HOME/tiramisu/snapshot.toml or ~/.cache/tiramisu/snapshot.toml
+pub fn snapshot() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or(PathBuf::from("."))
+        .join("tiramisu")
+        .join("snapshot.toml")
+}
+
+/// Default path to ssh config, ~/.ssh/config
+pub fn ssh_config() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or(PathBuf::from("."))
+        .join(".ssh")
+        .join("config")
+}
@@ -0,0 +1,321 @@
+//! Snapshots of running sessions, so that they may be recreated after tmux server dies
+//! or exported to config
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{self, Server},
+    tmux::{self, list},
+    ui,
+};
+
+/// Shells are not recorded as commands, they are started anyway
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "fish", "dash", "ksh", "tcsh", "nu"];
+
+/// Saved sessions, stored in the same format as sessions in config
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Snapshot {
+    #[serde(default)]
+    pub session: Vec<config::Session>,
+}
+
+/// Records windows, panes, directories, layouts and commands of running session
+pub fn capture(session: &list::SessionInfo, server: &Server) -> anyhow::Result<config::Session> {
+    let name = &session.name;
+    let windows = tmux::list_windows(name, server)?;
+    let panes = tmux::list_panes(name, server)?;
+
+    let mut focus = None;
+    let mut captured = Vec::new();
+    for (i, window) in windows.iter().enumerate() {
+        let panes: Vec<_> = panes.iter().filter(|p| p.window == window.id).collect();
+        let Some(first) = panes.first() else {
+            continue;
+        };
+        let active = panes.iter().position(|p| p.active).unwrap_or_default();
+        if window.active && (i, active) != (0, 0) {
+            focus = Some(config::Focus {
+                window: window.name.clone(),
+                pane: (active > 0).then_some(active),
+                always: false,
+            });
+        }
+
+        captured.push(config::Window {
+            name: window.name.clone(),
+            command: command(first),
+            dir: relative(&first.path, &session.path),
+            env: Default::default(),
+            options: Default::default(),
+            kill: Vec::new(),
+            container: String::new(),
+            layout: match panes.len() {
+                1 => String::new(),
+                _ => window.layout.clone(),
+            },
+            pane: panes[1..]
+                .iter()
+                .map(|p| config::Pane {
+                    command: command(p),
+                    dir: relative(&p.path, &first.path),
+                })
+                .collect(),
+        });
+    }
+
+    Ok(config::Session {
+        root: session.path.clone(),
+        name: name.clone(),
+        tags: Vec::new(),
+        description: String::new(),
+        server: server.clone(),
+        env: Default::default(),
+        options: Default::default(),
+        tmux_commands: Vec::new(),
+        view: false,
+        focus,
+        window: captured,
+    })
+}
+
+/// Records all sessions of given servers, returns number of saved sessions
+/// Nothing is written if there are no sessions, so that snapshot outlives dead server
+/// Session may be killed while it is captured, so failed ones are reported and skipped
+pub fn save(path: &Path, servers: &[Server]) -> anyhow::Result<usize> {
+    let mut snapshot = Snapshot::default();
+    for server in servers {
+        // views share windows with sessions they view
+        for session in tmux::list_sessions(server)?
+            .iter()
+            .filter(|s| s.view.is_empty())
+        {
+            match capture(session, server) {
+                Ok(captured) => snapshot.session.push(captured),
+                Err(e) => log::warn!(
+                    session = session.name.as_str();
+                    "Failed to capture {}: {:#}",
+                    session.name,
+                    e
+                ),
+            }
+        }
+    }
+    if snapshot.session.is_empty() {
+        return Ok(0);
+    }
+
+    std::fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+    // write whole file at once, so that crash does not leave partial snapshot
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, toml::to_string(&snapshot)?)?;
+    std::fs::rename(tmp, path)?;
+    Ok(snapshot.session.len())
+}
+
+/// Recreates saved sessions, which are not running
+/// Returns names of restored and failed sessions, sessions of unreachable servers fail
+pub fn restore(path: &Path) -> anyhow::Result<(Vec<String>, Vec<String>)> {
+    let snapshot: Snapshot = toml::from_str(&std::fs::read_to_string(path)?)?;
+    let (mut restored, mut failed) = (Vec::new(), Vec::new());
+    // names of running sessions per server, none for unreachable servers
+    let mut running: std::collections::HashMap<_, Option<Vec<String>>> = Default::default();
+    for session in snapshot.session {
+        let names = match running.get(&session.server) {
+            Some(names) => names,
+            None => {
+                let names = match tmux::list_sessions(&session.server) {
+                    Ok(sessions) => Some(sessions.into_iter().map(|s| s.name).collect()),
+                    Err(e) => {
+                        log::error!(
+                            "Failed to list sessions of {} server: {:#}",
+                            session.server,
+                            e
+                        );
+                        None
+                    }
+                };
+                running.entry(session.server.clone()).or_insert(names)
+            }
+        };
+        let Some(names) = names else {
+            failed.push(session.name);
+            continue;
+        };
+        if names.contains(&session.name) {
+            continue;
+        }
+        match tmux::create_session(&session.name, &ui::Session::new(&session, ui::State::None)) {
+            true => restored.push(session.name),
+            false => failed.push(session.name),
+        }
+    }
+    Ok((restored, failed))
+}
+
+/// Captures running session as `[[session]]` block of config
+pub fn export(name: &str, server: &Server) -> anyhow::Result<String> {
+    let session = tmux::list_sessions(server)?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or(anyhow::anyhow!("Session {} is not running", name))?;
+    let snapshot = Snapshot {
+        session: vec![escape(capture(&session, server)?)],
+    };
+    Ok(toml::to_string(&snapshot)?)
+}
+
+/// Escapes `$` in captured session, config expands variables, while snapshot is used as is
+/// Root is also glob, so its metacharacters are escaped too
+fn escape(session: config::Session) -> config::Session {
+    let path = |p: PathBuf| PathBuf::from(config::escape_env(&p.to_string_lossy()));
+    config::Session {
+        root: config::escape_root(&session.root.to_string_lossy()).into(),
+        name: config::escape_env(&session.name),
+        focus: session.focus.map(|f| config::Focus {
+            window: config::escape_env(&f.window),
+            ..f
+        }),
+        window: session
+            .window
+            .into_iter()
+            .map(|w| config::Window {
+                name: config::escape_env(&w.name),
+                command: config::escape_env(&w.command),
+                dir: path(w.dir),
+                pane: w
+                    .pane
+                    .into_iter()
+                    .map(|p| config::Pane {
+                        command: config::escape_env(&p.command),
+                        dir: path(p.dir),
+                    })
+                    .collect(),
+                ..w
+            })
+            .collect(),
+        ..session
+    }
+}
+
+/// Returns command to start pane with
+fn command(pane: &list::PaneInfo) -> String {
+    if !pane.start_command.is_empty() {
+        // tmux quotes arguments of start command
+        return match shell_words::split(&pane.start_command) {
+            Ok(args) if args.len() == 1 => args[0].clone(),
+            Ok(args) => shell_words::join(args),
+            Err(_) => pane.start_command.clone(),
+        };
+    }
+    match SHELLS.contains(&pane.command.as_str()) {
+        true => String::new(),
+        false => pane.command.clone(),
+    }
+}
+
+fn relative(path: &Path, base: &Path) -> PathBuf {
+    path.strip_prefix(base)
+        .map(Path::to_path_buf)
+        .unwrap_or(path.to_path_buf())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tmux::test::TestServer;
+
+    #[test]
+    fn test_save_and_restore() {
+        let Some(server) = TestServer::new("snapshot") else {
+            return;
+        };
+        let root = std::env::temp_dir().join(format!("tiramisu-snapshot-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        let mut session = server.session(&["editor", "shell"], ui::State::None);
+        session.root = root.clone();
+        session.windows[0].command = "sleep 100".into();
+        session.windows[1].pane = vec![config::Pane {
+            command: "sleep 200; echo 'a b'".into(),
+            dir: "sub".into(),
+        }];
+        session.focus = Some(config::Focus {
+            window: "shell".into(),
+            pane: Some(1),
+            always: false,
+        });
+        assert!(tmux::create_session("s", &session));
+        let layout = server.run(&["display", "-p", "-t", "=s:shell", "#{window_layout}"]);
+
+        let path = root.join("snapshot.toml");
+        assert_eq!(save(&path, std::slice::from_ref(&server.0)).unwrap(), 2);
+        let saved: Snapshot = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let s = saved.session.iter().find(|s| s.name == "s").unwrap();
+        assert_eq!(s.root, root);
+        assert_eq!(s.window[0].command, "sleep 100");
+        assert_eq!(s.window[1].command, "");
+        assert_eq!(s.window[1].layout, layout.trim());
+        assert_eq!(s.window[1].pane[0].command, "sleep 200; echo 'a b'");
+        assert_eq!(s.window[1].pane[0].dir, PathBuf::from("sub"));
+        assert_eq!(s.focus, session.focus);
+
+        // unreachable server does not stop restoring sessions of other ones
+        let mut snapshot: Snapshot =
+            toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let mut lost = snapshot.session[0].clone();
+        lost.name = "lost".into();
+        lost.server.socket_path = Some(root.join("a".repeat(200)));
+        snapshot.session.insert(0, lost);
+        std::fs::write(&path, toml::to_string(&snapshot).unwrap()).unwrap();
+
+        // running sessions are left as is
+        server.run(&["kill-session", "-t", "=s"]);
+        let (restored, failed) = restore(&path).unwrap();
+        assert_eq!(
+            (restored, failed),
+            (vec!["s".to_string()], vec!["lost".to_string()])
+        );
+        let panes = server.run(&[
+            "list-panes",
+            "-s",
+            "-t",
+            "=s",
+            "-F",
+            "#W #{pane_start_path}",
+        ]);
+        let expected = format!("editor {0}\nshell {0}\nshell {0}/sub\n", root.display());
+        assert_eq!(panes, expected);
+        let current = server.run(&["display", "-p", "-t", "=s:", "#W #P"]);
+        assert_eq!(current, "shell 1\n");
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_export() {
+        let Some(server) = TestServer::new("export") else {
+            return;
+        };
+        // root is expanded as glob, so its metacharacters must not match other dirs
+        let root = std::env::temp_dir().join(format!("tiramisu-export-{}", std::process::id()));
+        let dir = root.join("$x [a]*?");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(root.join("$x ab")).unwrap();
+        let mut session = server.session(&["a", "b"], ui::State::None);
+        session.root = dir.clone();
+        session.windows[1].command = "echo $HOME; sleep 100".into();
+        assert!(tmux::create_session("s", &session));
+        assert!(export("missing", &server.0).is_err());
+
+        // exported session expands to the same one
+        let exported: Snapshot = toml::from_str(&export("s", &server.0).unwrap()).unwrap();
+        let mut expanded = exported.session[0].expand().unwrap();
+        let first = expanded.next().unwrap().unwrap();
+        assert_eq!(first.name, "s");
+        assert_eq!(first.root, dir);
+        assert_eq!(first.window[1].name, "b");
+        assert_eq!(first.window[1].command, "echo $HOME; sleep 100");
+        assert!(expanded.next().is_none());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,276 @@
+//! Directories visited recently, listed as sessions in addition to configured ones
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+
+use crate::config::{self, SourceKind};
+
+/// Maximum depth of `Include` in ssh config
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Reads directories or hosts of source and turns each of them into session
+pub fn sessions(source: &config::Source) -> anyhow::Result<Vec<config::Session>> {
+    let home = dirs::home_dir().unwrap_or(PathBuf::from("/"));
+    // each item is session root and `$1`
+    let items: Vec<(PathBuf, String)> = match &source.kind {
+        SourceKind::Zoxide => directories(&zoxide()?)
+            .map(|d| (d.clone(), name(&d)))
+            .collect(),
+        SourceKind::List { path } => {
+            let text = std::fs::read_to_string(expand_home(path))
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            directories(&text).map(|d| (d.clone(), name(&d))).collect()
+        }
+        SourceKind::Ssh {
+            config,
+            known_hosts,
+        } => {
+            let config = expand_home(config);
+            let mut hosts = Vec::new();
+            ssh_hosts(&config, &home.join(".ssh"), 0, &mut hosts)?;
+            if *known_hosts {
+                let path = config.with_file_name("known_hosts");
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                for host in self::known_hosts(&text) {
+                    if !hosts.contains(&host) {
+                        hosts.push(host);
+                    }
+                }
+            }
+            // host is substituted into shell command of window
+            hosts
+                .into_iter()
+                .filter(|h| {
+                    let plain = shell_words::quote(h) == h.as_str();
+                    if !plain {
+                        log::warn!("Skipping ssh host {:?}, it needs quoting in shell", h);
+                    }
+                    plain
+                })
+                .map(|h| (home.clone(), h))
+                .collect()
+        }
+    };
+
+    let window = match (&source.kind, source.window.is_empty()) {
+        (_, false) => source.window.clone(),
+        (SourceKind::Ssh { .. }, true) => config::default_windows("ssh", "ssh $1"),
+        (_, true) => config::default_windows("shell", ""),
+    };
+    let template = config::Session {
+        root: PathBuf::new(),
+        name: source.prefix.clone() + "$1",
+        tags: source.tags.clone(),
+        description: String::new(),
+        server: source.server.clone(),
+        env: Default::default(),
+        options: Default::default(),
+        tmux_commands: Vec::new(),
+        view: false,
+        focus: None,
+        window,
+    };
+    let mut sessions = Vec::new();
+    for (root, value) in items.into_iter().take(source.limit.unwrap_or(usize::MAX)) {
+        match template.instantiate(&root, &vec![value.clone(), value]) {
+            Ok(session) => sessions.push(session),
+            Err(e) => log::warn!("Skipping {}: {}", root.display(), e),
+        }
+    }
+    Ok(sessions)
+}
+
+fn zoxide() -> anyhow::Result<String> {
+    let output = std::process::Command::new("zoxide")
+        .args(["query", "-l"])
+        .output()
+        .context("failed to run zoxide")?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // empty database is reported as error
+    if !output.status.success() && !stderr.contains("no match") {
+        bail!("zoxide failed: {}", stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parses directory per line, empty lines and `#` comments are skipped,
+/// so are directories, which do not exist anymore
+fn directories(text: &str) -> impl Iterator<Item = PathBuf> + '_ {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| expand_home(Path::new(line)))
+        .filter(|dir| dir.is_absolute() && dir.is_dir())
+}
+
+/// Collects hosts of ssh config, patterns are skipped
+/// Relative `Include` paths are relative to `dir`, which is `~/.ssh` for user config
+fn ssh_hosts(path: &Path, dir: &Path, depth: usize, hosts: &mut Vec<String>) -> anyhow::Result<()> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    for line in text.lines() {
+        let line = line.trim();
+        let (keyword, args) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or((line, ""));
+        let args = args.trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+        match keyword.to_lowercase().as_str() {
+            "host" => {
+                for host in args.split_whitespace() {
+                    if !host.contains(['*', '?', '!']) && !hosts.iter().any(|h| h == host) {
+                        hosts.push(host.to_string());
+                    }
+                }
+            }
+            "include" if depth < MAX_INCLUDE_DEPTH => {
+                for pattern in args.split_whitespace() {
+                    let pattern = dir.join(expand_home(Path::new(pattern)));
+                    for entry in capturing_glob::glob(&pattern.to_string_lossy())? {
+                        ssh_hosts(entry?.path(), dir, depth + 1, hosts)?;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Parses hosts of known_hosts, hashed hosts and hosts with non-default port are skipped
+fn known_hosts(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', '@', '|']))
+        .flat_map(|line| {
+            line.split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .split(',')
+        })
+        .filter_map(|host| match host.strip_prefix('[') {
+            Some(host) => host.strip_suffix("]:22"),
+            None => Some(host),
+        })
+        .filter(|host| !host.is_empty() && !host.contains(['*', '?', '!']))
+        .map(str::to_string)
+}
+
+/// Session name of directory, home directory is replaced with `~`
+fn name(dir: &Path) -> String {
+    match dirs::home_dir().and_then(|home| dir.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".into(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => dir.display().to_string(),
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_directories() {
+        let root = std::env::temp_dir().join(format!("tiramisu-source-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("a.b")).unwrap();
+        std::fs::create_dir_all(root.join("c")).unwrap();
+        let list = root.join("dirs");
+        let text = format!(
+            "# recent\n{0}/a.b\n\n  {0}/c  \n{0}/missing\nrelative\n{0}/dirs\n",
+            root.display()
+        );
+        std::fs::write(&list, &text).unwrap();
+
+        let dirs: Vec<_> = directories(&text).collect();
+        assert_eq!(dirs, [root.join("a.b"), root.join("c")]);
+
+        let toml = format!(
+            "type = 'list'\npath = '{}'\nprefix = 'z/'\nlimit = 1\nsocket_name = 's'",
+            list.display()
+        );
+        let source: config::Source = toml::from_str(&toml).unwrap();
+        assert_eq!(source.refresh_interval, chrono::Duration::seconds(30));
+        let sessions = sessions(&source).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].window[0].name, "shell");
+        assert_eq!(sessions[0].root, root.join("a.b"));
+        assert_eq!(
+            sessions[0].name,
+            format!("z/{}/a_b", root.display()).replace('.', "_")
+        );
+        assert_eq!(sessions[0].server.socket_name.as_deref(), Some("s"));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_ssh() {
+        let root = std::env::temp_dir().join(format!("tiramisu-ssh-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("config.d")).unwrap();
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        let config = format!(
+            "Include {}/config.d/*\nHost db-1 db-2 *.internal\n  User root\nhost=web.example.com\n\
+             Host x;reboot\nHost *\n",
+            root.display()
+        );
+        std::fs::write(root.join("config"), config).unwrap();
+        std::fs::write(root.join("config.d/work"), "  Host  gw !gw-old\n").unwrap();
+        std::fs::write(
+            root.join("known_hosts"),
+            "db-1,10.0.0.1 ssh-ed25519 AAAA\n[git]:22 ssh-rsa AAAA\n[alt]:2222 ssh-rsa AAAA\n\
+             |1|hash= ssh-rsa AAAA\n@revoked old ssh-rsa AAAA\n",
+        )
+        .unwrap();
+
+        let toml = format!(
+            "type = 'ssh'\nconfig = '{}'\nprefix = 'ssh/'",
+            root.join("config").display()
+        );
+        let mut source: config::Source = toml::from_str(&toml).unwrap();
+        let names = |source: &config::Source| -> Vec<String> {
+            sessions(source)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.name)
+                .collect()
+        };
+        assert_eq!(
+            names(&source),
+            ["ssh/gw", "ssh/db-1", "ssh/db-2", "ssh/web_example_com"]
+        );
+
+        source.kind = SourceKind::Ssh {
+            config: root.join("config"),
+            known_hosts: true,
+        };
+        let sessions = sessions(&source).unwrap();
+        let names: Vec<_> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names[4..], ["ssh/10_0_0_1", "ssh/git"]);
+        assert_eq!(sessions[1].root, dirs::home_dir().unwrap());
+        assert_eq!(sessions[1].window[0].command, "ssh db-1");
+
+        // relative include of nested file is relative to ssh dir, not to its own dir
+        std::fs::write(root.join("nested/config"), "Include config.d/*\n").unwrap();
+        let mut hosts = Vec::new();
+        ssh_hosts(&root.join("nested/config"), &root, 0, &mut hosts).unwrap();
+        assert_eq!(hosts, ["gw"]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_name() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(name(&home), "~");
+        assert_eq!(name(&home.join("src/app")), "~/src/app");
+        assert_eq!(name(Path::new("/srv/app")), "/srv/app");
+        assert_eq!(expand_home(Path::new("~/src")), home.join("src"));
+        assert_eq!(expand_home(Path::new("/~")), PathBuf::from("/~"));
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+    time::Duration,
+};
+
+use anyhow::anyhow;
+
+/// How long to wait for reply to a command
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Persistent connection to tmux server
+static CONNECTION: Mutex<Option<Control>> = Mutex::new(None);
+
+/// Reply to a command, either output lines or error message
+pub type Reply = Result<Vec<String>, String>;
+
+/// Notification sent by tmux server
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    /// Session was created or destroyed
+    SessionsChanged,
+    /// Session `$id` was renamed
+    SessionRenamed { id: String, name: String },
+    /// Window `@id` was added
+    WindowAdd { id: String },
+    /// Window `@id` was closed
+    WindowClose { id: String },
+    /// Some client switched session or detached
+    ClientChanged,
+    /// Connection is closed
+    Exit(Option<String>),
+}
+
+/// Line of control mode output
+#[derive(Debug, PartialEq)]
+enum Line {
+    Begin { number: String, own: bool },
+    End { number: String, error: bool },
+    Notification(Notification),
+    Unknown,
+}
+
+/// Control mode client (`tmux -C`), runs commands over single connection
+pub struct Control {
+    child: Child,
+    stdin: ChildStdin,
+    replies: Receiver<Reply>,
+}
+
+impl Control {
+    /// Connects to tmux server, attaching to given session
+    /// `notify` is called from background thread for each notification
+    pub fn new<F>(session: &str, notify: F) -> anyhow::Result<Self>
+    where
+        F: Fn(Notification) + Send + 'static,
+    {
+        let mut child = Command::new("tmux")
+            .args(["-C", "attach", "-f", "no-output,ignore-size", "-t", session])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or(anyhow!("no stdin"))?;
+        let stdout = child.stdout.take().ok_or(anyhow!("no stdout"))?;
+
+        let (tx, replies) = mpsc::channel();
+        std::thread::spawn(move || read(BufReader::new(stdout), tx, notify));
+
+        Ok(Self {
+            child,
+            stdin,
+            replies,
+        })
+    }
+
+    /// Runs single command, returns its output lines
+    pub fn command<S: AsRef<str>, I: IntoIterator<Item = S>>(
+        &mut self,
+        args: I,
+    ) -> anyhow::Result<Reply> {
+        let line = args
+            .into_iter()
+            .map(|s| quote(s.as_ref()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(self.stdin, "{}", line)?;
+        self.stdin.flush()?;
+        Ok(self.replies.recv_timeout(TIMEOUT)?)
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Opens global connection, which is used by `Tmux::run` afterwards
+pub fn connect<F>(session: &str, notify: F) -> anyhow::Result<()>
+where
+    F: Fn(Notification) + Send + 'static,
+{
+    *CONNECTION.lock().unwrap() = Some(Control::new(session, notify)?);
+    Ok(())
+}
+
+/// Runs commands over global connection, if it is open
+/// Returns None, if there is no connection
+pub fn run(commands: &[&[String]]) -> Option<anyhow::Result<Vec<Reply>>> {
+    let mut connection = CONNECTION.lock().unwrap();
+    let control = connection.as_mut()?;
+    let mut replies = Vec::new();
+    for command in commands {
+        match control.command(command.iter()) {
+            Ok(reply) => replies.push(reply),
+            Err(e) => {
+                // connection is broken, fallback to spawning processes
+                *connection = None;
+                return Some(Err(e));
+            }
+        }
+    }
+    Some(Ok(replies))
+}
+
+/// Quotes argument for tmux command parser
+/// Newline would end command, so it is escaped in double quotes between single quoted parts
+fn quote(s: &str) -> String {
+    s.split('\n')
+        .map(|part| format!("'{}'", part.replace('\'', "'\"'\"'")))
+        .collect::<Vec<_>>()
+        .join("\"\\n\"")
+}
+
+fn read<R: BufRead, F: Fn(Notification)>(reader: R, replies: Sender<Reply>, notify: F) {
+    // number of command, whether it was issued by us, output lines
+    let mut block: Option<(String, bool, Vec<String>)> = None;
+    let mut reason = None;
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        // inside of block everything except matching end is output
+        if let Some((number, own, output)) = block.as_mut() {
+            match parse(&line) {
+                Line::End { number: n, error } if n == *number => {
+                    let output = std::mem::take(output);
+                    if *own {
+                        let reply = match error {
+                            false => Ok(output),
+                            true => Err(output.join("\n")),
+                        };
+                        let _ = replies.send(reply);
+                    }
+                    block = None;
+                }
+                _ => output.push(line),
+            }
+            continue;
+        }
+
+        match parse(&line) {
+            Line::Begin { number, own } => block = Some((number, own, Vec::new())),
+            Line::Notification(Notification::Exit(r)) => {
+                reason = r;
+                break;
+            }
+            Line::Notification(n) => notify(n),
+            Line::End { .. } | Line::Unknown => (),
+        }
+    }
+    notify(Notification::Exit(reason));
+}
+
+fn parse(line: &str) -> Line {
+    let mut words = line.split(' ');
+    let kind = words.next().unwrap_or_default();
+    let mut arg = || words.next().unwrap_or_default().to_string();
+    match kind {
+        "%begin" | "%end" | "%error" => {
+            let (_time, number, flags) = (arg(), arg(), arg());
+            if kind == "%begin" {
+                let own = flags.parse::<u32>().is_ok_and(|f| f & 1 != 0);
+                Line::Begin { number, own }
+            } else {
+                Line::End {
+                    number,
+                    error: kind == "%error",
+                }
+            }
+        }
+        "%sessions-changed" => Line::Notification(Notification::SessionsChanged),
+        "%session-renamed" => {
+            let id = arg();
+            let name = line.splitn(3, ' ').nth(2).unwrap_or_default().to_string();
+            Line::Notification(Notification::SessionRenamed { id, name })
+        }
+        "%window-add" | "%unlinked-window-add" => {
+            Line::Notification(Notification::WindowAdd { id: arg() })
+        }
+        "%window-close" | "%unlinked-window-close" => {
+            Line::Notification(Notification::WindowClose { id: arg() })
+        }
+        "%client-session-changed" | "%client-detached" => {
+            Line::Notification(Notification::ClientChanged)
+        }
+        "%exit" => {
+            let reason = line.split_once(' ').map(|(_, r)| r.to_string());
+            Line::Notification(Notification::Exit(reason))
+        }
+        _ => Line::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Feeds output to reader, returns replies and notifications it produced
+    fn read_all(output: &str) -> (Vec<Reply>, Vec<Notification>) {
+        let (tx, rx) = mpsc::channel();
+        let notifications = Mutex::new(Vec::new());
+        read(Cursor::new(output), tx, |n| {
+            notifications.lock().unwrap().push(n)
+        });
+        (rx.try_iter().collect(), notifications.into_inner().unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("%begin 1700000000 12 1"),
+            Line::Begin {
+                number: "12".into(),
+                own: true
+            }
+        );
+        assert_eq!(
+            parse("%begin 1700000000 12 0"),
+            Line::Begin {
+                number: "12".into(),
+                own: false
+            }
+        );
+        assert_eq!(
+            parse("%error 1700000000 12 1"),
+            Line::End {
+                number: "12".into(),
+                error: true
+            }
+        );
+        assert_eq!(
+            parse("%session-renamed $2 name with spaces"),
+            Line::Notification(Notification::SessionRenamed {
+                id: "$2".into(),
+                name: "name with spaces".into()
+            })
+        );
+        assert_eq!(
+            parse("%unlinked-window-close @7"),
+            Line::Notification(Notification::WindowClose { id: "@7".into() })
+        );
+        assert_eq!(
+            parse("%client-detached /dev/pts/1"),
+            Line::Notification(Notification::ClientChanged)
+        );
+        assert_eq!(parse("%exit"), Line::Notification(Notification::Exit(None)));
+        assert_eq!(parse("%output %1 text"), Line::Unknown);
+        assert_eq!(parse(""), Line::Unknown);
+    }
+
+    #[test]
+    fn test_framing() {
+        let output = [
+            // reply to attach is not ours
+            "%begin 1700000000 10 0",
+            "%end 1700000000 10 0",
+            "%begin 1700000000 11 1",
+            "line 1",
+            // inside of block notifications and other ends are output
+            "%sessions-changed",
+            "%end 1700000000 12 1",
+            "%end 1700000000 11 1",
+            "%window-add @3",
+            "%begin 1700000000 13 1",
+            "unknown command: foo",
+            "second line",
+            "%error 1700000000 13 1",
+            "%begin 1700000000 14 1",
+            "%end 1700000000 14 1",
+            "%session-renamed $1 new name",
+            "%exit server exited",
+            "%sessions-changed",
+        ]
+        .join("\n");
+
+        let (replies, notifications) = read_all(&output);
+        assert_eq!(
+            replies,
+            [
+                Ok(vec![
+                    "line 1".to_string(),
+                    "%sessions-changed".to_string(),
+                    "%end 1700000000 12 1".to_string()
+                ]),
+                Err("unknown command: foo\nsecond line".to_string()),
+                Ok(vec![]),
+            ]
+        );
+        assert_eq!(
+            notifications,
+            [
+                Notification::WindowAdd { id: "@3".into() },
+                Notification::SessionRenamed {
+                    id: "$1".into(),
+                    name: "new name".into()
+                },
+                Notification::Exit(Some("server exited".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_closed() {
+        // connection closed in the middle of reply, it is never sent
+        let (replies, notifications) = read_all("%begin 1700000000 11 1\nline 1\n");
+        assert!(replies.is_empty());
+        assert_eq!(notifications, [Notification::Exit(None)]);
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("a b; c"), "'a b; c'");
+        assert_eq!(quote("$HOME #{pane_id}"), "'$HOME #{pane_id}'");
+        assert_eq!(quote("it's"), r#"'it'"'"'s'"#);
+        assert_eq!(quote("a\nb c\n"), r#"'a'"\n"'b c'"\n"''"#);
+    }
+}
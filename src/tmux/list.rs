@@ -0,0 +1,288 @@
+//! Typed output of tmux `list-*` commands
+//! Fields are separated by unit separator, tmux escapes control characters in names,
+//! but not in paths and commands, so line with more fields than expected is rejected
+//! instead of shifting fields
+
+use anyhow::{Context, anyhow, bail};
+use std::{collections::HashSet, path::PathBuf};
+
+const SEPARATOR: &str = "\x1f";
+
+/// Item of tmux list, which may be parsed from format fields
+pub trait Format: Sized {
+    /// tmux format variables
+    const FIELDS: &'static [&'static str];
+
+    /// Parses item from fields, there are exactly `FIELDS.len()` of them
+    fn parse(fields: &[&str]) -> anyhow::Result<Self>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionInfo {
+    /// Session id, `$n`
+    pub id: String,
+    /// Number of attached clients
+    pub attached: usize,
+    /// Number of windows
+    pub windows: usize,
+    /// Working directory of session
+    pub path: PathBuf,
+    /// Name of session group, empty if session is not grouped
+    pub group: String,
+    /// For grouped view session, name of session it views
+    pub view: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowInfo {
+    /// Window id, `@n`
+    pub id: String,
+    pub index: usize,
+    pub active: bool,
+    /// Layout of panes
+    pub layout: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaneInfo {
+    /// Pane id, `%n`
+    pub id: String,
+    /// Id of window pane belongs to
+    pub window: String,
+    pub index: usize,
+    pub active: bool,
+    /// Command pane was started with, empty for default shell
+    pub start_command: String,
+    /// Command running in pane
+    pub command: String,
+    /// Working directory of pane
+    pub path: PathBuf,
+}
+
+impl Format for SessionInfo {
+    const FIELDS: &'static [&'static str] = &[
+        "session_id",
+        "session_attached",
+        "session_windows",
+        "session_path",
+        "session_group",
+        super::VIEW_OPTION,
+        "session_name",
+    ];
+
+    fn parse(fields: &[&str]) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: id(fields[0], '$')?,
+            attached: number(fields[1])?,
+            windows: number(fields[2])?,
+            path: fields[3].into(),
+            group: fields[4].to_string(),
+            view: fields[5].to_string(),
+            name: fields[6].to_string(),
+        })
+    }
+}
+
+impl SessionInfo {
+    /// Returns name of existing session this one is a view of: one it is tagged with,
+    /// or one its group is named after, as `new-session -t` names group after its target
+    pub fn viewed(&self, names: &HashSet<String>) -> Option<&str> {
+        [&self.view, &self.group]
+            .into_iter()
+            .find(|n| !n.is_empty() && **n != self.name && names.contains(*n))
+            .map(|n| n.as_str())
+    }
+}
+
+impl Format for WindowInfo {
+    const FIELDS: &'static [&'static str] = &[
+        "window_id",
+        "window_index",
+        "window_active",
+        "window_layout",
+        "window_name",
+    ];
+
+    fn parse(fields: &[&str]) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: id(fields[0], '@')?,
+            index: number(fields[1])?,
+            active: flag(fields[2])?,
+            layout: fields[3].to_string(),
+            name: fields[4].to_string(),
+        })
+    }
+}
+
+impl Format for PaneInfo {
+    const FIELDS: &'static [&'static str] = &[
+        "pane_id",
+        "window_id",
+        "pane_index",
+        "pane_active",
+        "pane_start_command",
+        "pane_current_command",
+        "pane_current_path",
+    ];
+
+    fn parse(fields: &[&str]) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: id(fields[0], '%')?,
+            window: id(fields[1], '@')?,
+            index: number(fields[2])?,
+            active: flag(fields[3])?,
+            start_command: fields[4].to_string(),
+            command: fields[5].to_string(),
+            path: fields[6].into(),
+        })
+    }
+}
+
+/// Returns `-F` argument for items of type `T`
+pub fn format<T: Format>() -> String {
+    T::FIELDS
+        .iter()
+        .map(|f| format!("#{{{}}}", f))
+        .collect::<Vec<_>>()
+        .join(SEPARATOR)
+}
+
+/// Parses output of list command, which was run with `format::<T>()`
+pub fn parse<T: Format>(output: &str) -> anyhow::Result<Vec<T>> {
+    output
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split(SEPARATOR).collect();
+            if fields.len() != T::FIELDS.len() {
+                bail!("expected {} fields, got {}", T::FIELDS.len(), fields.len());
+            }
+            T::parse(&fields)
+        })
+        .enumerate()
+        .map(|(i, item)| item.with_context(|| format!("unexpected tmux output at line {}", i + 1)))
+        .collect()
+}
+
+fn id(s: &str, prefix: char) -> anyhow::Result<String> {
+    match s.strip_prefix(prefix).map(|n| n.parse::<usize>()) {
+        Some(Ok(_)) => Ok(s.to_string()),
+        _ => Err(anyhow!("invalid id {:?}", s)),
+    }
+}
+
+fn number(s: &str) -> anyhow::Result<usize> {
+    s.parse().with_context(|| format!("invalid number {:?}", s))
+}
+
+fn flag(s: &str) -> anyhow::Result<bool> {
+    match s {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(anyhow!("invalid flag {:?}", s)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn line(fields: &[&str]) -> String {
+        fields.join(SEPARATOR) + "\n"
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            format::<WindowInfo>(),
+            "#{window_id}\x1f#{window_index}\x1f#{window_active}\x1f#{window_layout}\x1f#{window_name}"
+        );
+    }
+
+    #[test]
+    fn test_parse_sessions() {
+        let output = line(&["$0", "1", "2", "/src/app", "", "", "src/app"])
+            + &line(&["$12", "0", "1", "/", "", "", "name with spaces"])
+            + &line(&["$3", "0", "1", "/", "", "", "name: 1 2"])
+            + &line(&["$4", "0", "1", "/", "app", "app", ""]);
+        let sessions = parse::<SessionInfo>(&output).unwrap();
+        assert_eq!(
+            sessions[0],
+            SessionInfo {
+                id: "$0".into(),
+                attached: 1,
+                windows: 2,
+                path: "/src/app".into(),
+                group: "".into(),
+                view: "".into(),
+                name: "src/app".into()
+            }
+        );
+        assert_eq!(sessions[1].name, "name with spaces");
+        assert_eq!(sessions[2].name, "name: 1 2");
+        assert_eq!(sessions[3].name, "");
+        assert_eq!(sessions[3].view, "app");
+        assert!(parse::<SessionInfo>("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_viewed() {
+        let output = line(&["$0", "0", "1", "/", "s", "", "s"])
+            + &line(&["$1", "0", "1", "/", "s", "s", "s+1"])
+            + &line(&["$2", "0", "1", "/", "s", "", "other"])
+            + &line(&["$3", "0", "1", "/", "gone", "", "orphan"]);
+        let sessions = parse::<SessionInfo>(&output).unwrap();
+        let names = sessions.iter().map(|s| s.name.clone()).collect();
+        let viewed: Vec<_> = sessions.iter().map(|s| s.viewed(&names)).collect();
+        assert_eq!(viewed, [None, Some("s"), Some("s"), None]);
+    }
+
+    #[test]
+    fn test_parse_windows_and_panes() {
+        let layout = "b25d,80x24,0,0,1";
+        let windows = parse::<WindowInfo>(&line(&["@1", "3", "1", layout, "nvim"])).unwrap();
+        assert_eq!(
+            windows,
+            [WindowInfo {
+                id: "@1".into(),
+                index: 3,
+                active: true,
+                layout: layout.into(),
+                name: "nvim".into()
+            }]
+        );
+        let panes =
+            parse::<PaneInfo>(&line(&["%5", "@1", "0", "0", "", "zsh", "/tmp/a b"])).unwrap();
+        assert_eq!(panes[0].window, "@1");
+        assert_eq!(panes[0].path, PathBuf::from("/tmp/a b"));
+        assert!(!panes[0].active);
+    }
+
+    #[test]
+    fn test_parse_unexpected() {
+        // old-style space separated output
+        assert!(parse::<SessionInfo>("main 1\n").is_err());
+        // missing fields
+        assert!(parse::<SessionInfo>(&line(&["$0", "1", "2", "/"])).is_err());
+        // separator inside of path would shift the rest of fields
+        let e = parse::<SessionInfo>(&line(&["$0", "1", "2", "/a\x1fb", "", "", "main"]));
+        assert_eq!(
+            format!("{:#}", e.unwrap_err()),
+            "unexpected tmux output at line 1: expected 7 fields, got 8"
+        );
+        let pane = line(&["%5", "@1", "0", "0", "a\x1fb", "zsh", "/"]);
+        assert!(parse::<PaneInfo>(&pane).is_err());
+        // not a number
+        assert!(parse::<SessionInfo>(&line(&["$0", "yes", "2", "/", "", "", "main"])).is_err());
+        assert!(parse::<SessionInfo>(&line(&["$0", "-1", "2", "/", "", "", "main"])).is_err());
+        // wrong id
+        assert!(parse::<SessionInfo>(&line(&["@0", "1", "2", "/", "", "", "main"])).is_err());
+        assert!(parse::<WindowInfo>(&line(&["@1", "0", "2", "", "nvim"])).is_err());
+
+        let output = line(&["$0", "1", "2", "/", "", "", "a"]) + "garbage\n";
+        let e = parse::<SessionInfo>(&output).unwrap_err();
+        assert_eq!(e.to_string(), "unexpected tmux output at line 2");
+    }
+}
@@ -0,0 +1,1060 @@
+use crate::{
+    config::{Focus, Server},
+    container,
+    ui::{Session, State},
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+pub mod control;
+pub mod list;
+pub mod target;
+
+/// User option of grouped view session, its value is name of viewed session
+const VIEW_OPTION: &str = "@tiramisu_view";
+
+/// Commands running longer are logged with debug level, others with trace one
+const SLOW_COMMAND: Duration = Duration::from_millis(100);
+
+pub struct Tmux {
+    server: Vec<String>,
+    args: Vec<String>,
+    client: bool,
+    nested: bool,
+    quiet: bool,
+}
+
+impl Tmux {
+    pub fn new(server: &Server) -> Self {
+        Self {
+            server: server.args(),
+            args: Vec::new(),
+            client: false,
+            nested: false,
+            quiet: false,
+        }
+    }
+
+    /// Creates command list, which refers to invoking client, so it is never sent over
+    /// control mode connection
+    pub fn client(server: &Server) -> Self {
+        Self {
+            client: true,
+            ..Self::new(server)
+        }
+    }
+
+    /// Runs tmux as if it was not inside of other tmux
+    pub fn nested(mut self) -> Self {
+        self.nested = true;
+        self
+    }
+
+    /// Do not report errors, they are expected
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    pub fn command<S: AsRef<str>, I: IntoIterator<Item = S>>(&mut self, it: I) {
+        for s in it {
+            let s = s.as_ref();
+            if !s.is_empty() {
+                self.args.push(s.to_string());
+            }
+        }
+        self.args.push(";".to_string());
+    }
+
+    /// Runs commands, failure is logged (as debug in quiet mode)
+    pub fn run(self, inherit: bool) -> Option<String> {
+        let quiet = self.quiet;
+        match self.timed(inherit) {
+            Ok(output) => Some(output),
+            Err(e) if quiet => {
+                log::debug!("tmux failed: {}", e);
+                None
+            }
+            Err(e) => {
+                log::error!("tmux failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Runs commands, failure is returned to caller instead of being logged
+    pub fn output(self) -> anyhow::Result<String> {
+        self.timed(false)
+    }
+
+    fn timed(self, inherit: bool) -> anyhow::Result<String> {
+        // attached client runs as long as session is attached, so it is not timed
+        if inherit {
+            log::trace!("tmux {:?} {:?}", self.server, self.args);
+            return self.execute(true);
+        }
+        // without debug level neither slow nor fast commands are logged, so it is not joined
+        let command = log::log_enabled!(log::Level::Debug)
+            .then(|| shell_words::join(self.server.iter().chain(&self.args)));
+        let start = Instant::now();
+        let output = self.execute(false);
+        let duration = start.elapsed();
+        let level = match duration >= SLOW_COMMAND {
+            true => log::Level::Debug,
+            false => log::Level::Trace,
+        };
+        if let Some(command) = command {
+            log::log!(
+                level,
+                command = command.as_str(),
+                duration_ms = duration.as_millis() as u64;
+                "tmux {} took {}ms",
+                command,
+                duration.as_millis()
+            );
+        }
+        output
+    }
+
+    fn execute(self, inherit: bool) -> anyhow::Result<String> {
+        // control mode connection is open only to current server
+        if !inherit && !self.client && self.server.is_empty() {
+            let commands: Vec<&[String]> = self
+                .args
+                .split(|s| s == ";")
+                .filter(|c| !c.is_empty())
+                .collect();
+            match control::run(&commands) {
+                Some(Ok(replies)) => return Self::join(replies),
+                Some(Err(e)) => log::warn!("tmux control mode connection lost: {}", e),
+                None => (),
+            }
+        }
+
+        let mut command = Command::new("tmux");
+        command.args(self.server);
+        if self.nested {
+            command.env_remove("TMUX");
+        }
+        if inherit {
+            command
+                .stdin(Stdio::inherit())
+                .stdout(Stdio::inherit())
+                .stderr(Stdio::inherit());
+        }
+        let r = command
+            .args(self.args)
+            .output()
+            .map_err(|e| anyhow::anyhow!("failed to run tmux: {}", e))?;
+        let stderr = Self::to_string(r.stderr);
+        // in quiet mode any output to stderr means failure
+        if !r.status.success() || (self.quiet && !stderr.is_empty()) {
+            return Err(anyhow::anyhow!("{} ({})", stderr.trim(), r.status));
+        }
+        if !stderr.is_empty() {
+            log::warn!("tmux stderr: {}", stderr);
+        }
+        Ok(Self::to_string(r.stdout))
+    }
+
+    pub fn attached() -> bool {
+        std::env::var("TMUX").is_ok()
+    }
+
+    /// Checks whether tiramisu runs inside of given server, default one included,
+    /// since `$TMUX` may point to server with custom socket
+    pub fn is_current(server: &Server) -> bool {
+        if !Tmux::attached() {
+            return false;
+        }
+        let current = std::env::var("TMUX").unwrap_or_default();
+        let mut tmux = Tmux::client(server).nested().quiet();
+        tmux.command(["display-message", "-p", "#{socket_path}"]);
+        tmux.run(false)
+            .is_some_and(|path| current.split(',').next() == Some(path.trim()))
+    }
+
+    /// Returns id of session tiramisu runs in
+    pub fn current_session() -> Option<String> {
+        let tmux = std::env::var("TMUX").ok()?;
+        tmux.rsplit(',').next().map(|id| format!("${}", id))
+    }
+
+    fn join(replies: Vec<control::Reply>) -> anyhow::Result<String> {
+        let mut output = String::new();
+        for reply in replies {
+            match reply {
+                Ok(lines) => lines.into_iter().for_each(|l| output += &(l + "\n")),
+                Err(e) => return Err(anyhow::anyhow!("{}", e)),
+            }
+        }
+        Ok(output)
+    }
+
+    fn to_string(v: Vec<u8>) -> String {
+        match String::from_utf8(v) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("tmux returned non-utf8: {:?}", e.into_bytes().as_slice());
+                String::new()
+            }
+        }
+    }
+}
+
+/// How session is opened
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+    /// Current client is switched to session, outside of tmux session is attached
+    Switch,
+    /// Current window of session is linked to current session as new window
+    Link,
+    /// Session is attached in popup over current client
+    Popup,
+    /// Session is attached in external terminal, `{}` in command is replaced with attach command
+    Terminal(String),
+}
+
+pub fn open(name: &str, session: &Session, mode: &Mode) -> bool {
+    // on creation focus is applied anyway
+    if session.state == State::None {
+        if !create_session(name, session) {
+            return false;
+        }
+    } else if let Some(f) = session.focus.as_ref().filter(|f| f.always) {
+        // session may be opened even if window is gone
+        focus(name, &session.server, f);
+    }
+
+    // linked window is shared anyway and other clients are detached from session itself,
+    // so view is not needed in either case
+    let view = match session.view && !session.detach && *mode != Mode::Link {
+        true => match create_view(name, &session.server) {
+            Some(view) => Some(view),
+            None => return false,
+        },
+        false => None,
+    };
+    let name = view.as_deref().unwrap_or(name);
+    let mut flags = attach_flags(session);
+    // `attach -d` in popup or terminal would detach client tiramisu runs in as well,
+    // so inside of its server other clients are detached beforehand instead
+    if session.detach && *mode != Mode::Link && Tmux::is_current(&session.server) {
+        if !detach_others(name, &session.server) {
+            return false;
+        }
+        flags.retain(|f| *f != "-d");
+    }
+    let opened = match mode {
+        // switched client would stay read-only, so session is shown in popup instead
+        Mode::Switch if session.read_only && Tmux::is_current(&session.server) => {
+            popup(name, &session.server, view.is_some(), &flags)
+        }
+        Mode::Switch => switch(name, &session.server, view.is_some(), &flags),
+        Mode::Link => link(name, &session.server),
+        Mode::Popup => popup(name, &session.server, view.is_some(), &flags),
+        Mode::Terminal(command) => terminal(command, name, &session.server, view.is_some(), &flags),
+    };
+    // view is destroyed once its client detaches, so it would be left if none attached
+    if let Some(view) = view.filter(|_| !opened) {
+        let mut tmux = Tmux::new(&session.server);
+        tmux.command(["kill-session", "-t", &target::session(&view)]);
+        tmux.run(false);
+    }
+    opened
+}
+
+/// Returns flags of `attach-session`
+fn attach_flags(session: &Session) -> Vec<&'static str> {
+    let mut flags = Vec::new();
+    if session.detach {
+        flags.push("-d");
+    }
+    if session.read_only {
+        flags.push("-r");
+    }
+    flags
+}
+
+/// Switches current client to session, outside of tmux session is attached
+/// View session is destroyed once its last client detaches
+fn switch(name: &str, server: &Server, view: bool, flags: &[&str]) -> bool {
+    let mut tmux = Tmux::client(server);
+    let current = Tmux::is_current(server);
+    match current {
+        true => tmux.command(["switch-client", "-t", &target::session(name)]),
+        false => {
+            let target = target::session(name);
+            tmux.command([["attach"].as_slice(), flags, &["-t", &target]].concat())
+        }
+    }
+    // unattached session would be destroyed right away
+    if view {
+        tmux.command([
+            "set-option",
+            "-t",
+            &target::window(name),
+            "destroy-unattached",
+            "on",
+        ]);
+    }
+    match current {
+        true => tmux.run(false).is_some(),
+        // sessions of other servers can only be attached
+        false => tmux.nested().run(true).is_some(),
+    }
+}
+
+/// Detaches clients attached to session, except for the one tiramisu runs in
+fn detach_others(name: &str, server: &Server) -> bool {
+    let mut tmux = Tmux::client(server);
+    tmux.command(["display-message", "-p", "#{client_name}"]);
+    tmux.command([
+        "list-clients",
+        "-t",
+        &target::session(name),
+        "-F",
+        "#{client_name}",
+    ]);
+    let output = match tmux.output() {
+        Ok(output) => output,
+        Err(e) => {
+            log::error!(
+                session = name;
+                "Failed to list clients of {}: {:#}",
+                name,
+                e
+            );
+            return false;
+        }
+    };
+    let mut lines = output.lines();
+    let current = lines.next();
+    let mut tmux = Tmux::client(server);
+    for client in lines.filter(|c| Some(*c) != current) {
+        tmux.command(["detach-client", "-t", client]);
+    }
+    tmux.args.is_empty() || tmux.run(false).is_some()
+}
+
+/// Links current window of session to current session
+fn link(name: &str, server: &Server) -> bool {
+    let current = Tmux::current_session().filter(|_| Tmux::is_current(server));
+    let Some(current) = current else {
+        log::error!(
+            session = name;
+            "Unable to link window of {}, which is not in current tmux server",
+            name
+        );
+        return false;
+    };
+    let mut tmux = Tmux::client(server);
+    let target = format!("{}:", current);
+    tmux.command([
+        "link-window",
+        "-a",
+        "-s",
+        &target::window(name),
+        "-t",
+        &target,
+    ]);
+    tmux.run(false).is_some()
+}
+
+/// Attaches session in popup of current client, popup is closed once client detaches
+fn popup(name: &str, server: &Server, view: bool, flags: &[&str]) -> bool {
+    if !Tmux::attached() {
+        log::error!("Unable to open popup outside of tmux");
+        return false;
+    }
+    // popup is shown by server of current client, which may be different one
+    let mut tmux = Tmux::client(&Server::default());
+    let attach = shell_words::join(attach_command(name, server, view, flags));
+    tmux.command(["display-popup", "-E", "-w", "90%", "-h", "90%", &attach]);
+    tmux.run(false).is_some()
+}
+
+/// Attaches session in new external terminal, which is not waited for
+fn terminal(command: &str, name: &str, server: &Server, view: bool, flags: &[&str]) -> bool {
+    if command.is_empty() {
+        log::error!("Unable to open terminal, `terminal` is not set in [tmux] config");
+        return false;
+    }
+    let attach = shell_words::join(attach_command(name, server, view, flags));
+    match terminal_command(command, &attach).spawn() {
+        Ok(_) => true,
+        Err(e) => {
+            log::error!("Failed to run terminal {:?}: {}", command, e);
+            false
+        }
+    }
+}
+
+/// Returns command, which runs terminal with attach command in place of `{}`
+fn terminal_command(command: &str, attach: &str) -> Command {
+    let mut terminal = Command::new("sh");
+    terminal
+        .args(["-c", &command.replace("{}", attach)])
+        .env_remove("TMUX")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // terminal outlives picker and its popup
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut terminal, 0);
+    terminal
+}
+
+/// Returns shell command, which attaches session outside of tmux
+fn attach_command(name: &str, server: &Server, view: bool, flags: &[&str]) -> Vec<String> {
+    let mut command = vec!["env".to_string(), "-u".into(), "TMUX".into(), "tmux".into()];
+    command.extend(server.args());
+    command.push("attach".into());
+    command.extend(flags.iter().map(|f| f.to_string()));
+    command.extend(["-t".into(), target::session(name)]);
+    if view {
+        command.extend([";", "set-option", "-t", &target::window(name)].map(Into::into));
+        command.extend(["destroy-unattached".into(), "on".into()]);
+    }
+    command
+}
+
+/// Creates session grouped with given one, it shares windows, but has its own current window
+/// Returns name of view
+pub fn create_view(name: &str, server: &Server) -> Option<String> {
+    let sessions = match list_sessions(server) {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            log::error!("Failed to list sessions: {:#}", e);
+            return None;
+        }
+    };
+    // with missing session, tmux would create new group
+    if sessions.iter().all(|s| s.name != name) {
+        log::error!(
+            session = name;
+            "Unable to view {} because it is not created",
+            name
+        );
+        return None;
+    }
+    let view = (1..)
+        .map(|i| format!("{}+{}", name, i))
+        .find(|v| sessions.iter().all(|s| &s.name != v))?;
+
+    let mut tmux = Tmux::new(server);
+    tmux.command([
+        "new-session",
+        "-d",
+        "-t",
+        &target::session(name),
+        "-s",
+        &view,
+    ]);
+    tmux.command([
+        "set-option",
+        "-t",
+        &target::window(&view),
+        VIEW_OPTION,
+        name,
+    ]);
+    tmux.run(false)?;
+    Some(view)
+}
+
+pub fn kill(name: &str, session: &Session) -> bool {
+    let mut tmux = Tmux::new(&session.server);
+    match session.state {
+        State::None => {
+            log::warn!(
+                session = name;
+                "Unable to kill {} because it is not created",
+                name
+            );
+            return false;
+        }
+        State::Attached => {
+            log::warn!(
+                session = name;
+                "Unable to kill {} because it is attached",
+                name
+            );
+            return false;
+        }
+        State::Created => (),
+    }
+
+    // views would outlive windows killed by keys, they are not needed anyway
+    match list_sessions(&session.server) {
+        Ok(sessions) => {
+            for view in sessions.iter().filter(|s| s.view == name) {
+                tmux.command(["kill-session", "-t", &target::session(&view.name)]);
+            }
+        }
+        Err(e) => log::error!(
+            session = name;
+            "Failed to list views of {}: {:#}",
+            name,
+            e
+        ),
+    }
+
+    // window indices depend on base-index and may be changed, so windows are killed by id
+    let windows = match list_windows(name, &session.server) {
+        Ok(windows) if !windows.is_empty() => windows,
+        Ok(_) => return false,
+        Err(e) => {
+            log::error!(
+                session = name;
+                "Failed to list windows of {}: {:#}",
+                name,
+                e
+            );
+            return false;
+        }
+    };
+    for (window, id) in session
+        .windows
+        .iter()
+        .zip(windows.into_iter().map(|w| w.id))
+    {
+        if window.kill.is_empty() {
+            tmux.command(["kill-window", "-t", &id]);
+            continue;
+        }
+        let mut command = Vec::from(["send-keys", "-t", &id]);
+        command.extend(window.kill.iter().map(|s| s.as_str()));
+        tmux.command(command);
+    }
+    tmux.run(false).is_some()
+}
+
+/// Lists sessions of server, server which is not running has no sessions
+pub fn list_sessions(server: &Server) -> anyhow::Result<Vec<list::SessionInfo>> {
+    let mut tmux = Tmux::new(server);
+    tmux.command(["ls", "-F", &list::format::<list::SessionInfo>()]);
+    match tmux.output() {
+        Ok(output) => list::parse(&output),
+        Err(e) if is_not_running(&e) => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Checks whether tmux failed because there is no server at its socket
+fn is_not_running(e: &anyhow::Error) -> bool {
+    let e = e.to_string();
+    e.starts_with("no server running on")
+        || (e.starts_with("error connecting to")
+            && (e.contains("No such file or directory") || e.contains("Connection refused")))
+}
+
+/// Lists windows of session in order
+pub fn list_windows(name: &str, server: &Server) -> anyhow::Result<Vec<list::WindowInfo>> {
+    let mut tmux = Tmux::new(server);
+    tmux.command([
+        "list-windows",
+        "-t",
+        &target::session(name),
+        "-F",
+        &list::format::<list::WindowInfo>(),
+    ]);
+    list::parse(&tmux.output()?)
+}
+
+/// Lists panes of all windows of session
+pub fn list_panes(name: &str, server: &Server) -> anyhow::Result<Vec<list::PaneInfo>> {
+    let mut tmux = Tmux::new(server);
+    tmux.command([
+        "list-panes",
+        "-s",
+        "-t",
+        &target::session(name),
+        "-F",
+        &list::format::<list::PaneInfo>(),
+    ]);
+    list::parse(&tmux.output()?)
+}
+
+/// Selects window and pane of session, window is looked up by name first, then by index
+pub fn focus(name: &str, server: &Server, focus: &Focus) -> bool {
+    let lookup = || -> anyhow::Result<String> {
+        let windows = list_windows(name, server)?;
+        let window = windows
+            .iter()
+            .find(|w| w.name == focus.window)
+            .or_else(|| windows.get(focus.window.parse::<usize>().ok()?))
+            .ok_or(anyhow::anyhow!("no window {:?}", focus.window))?;
+        let Some(pane) = focus.pane else {
+            return Ok(window.id.clone());
+        };
+        let panes = list_panes(name, server)?;
+        let pane = panes
+            .iter()
+            .filter(|p| p.window == window.id)
+            .nth(pane)
+            .ok_or(anyhow::anyhow!(
+                "no pane {} in window {:?}",
+                pane,
+                window.name
+            ))?;
+        Ok(pane.id.clone())
+    };
+    let id = match lookup() {
+        Ok(id) => id,
+        Err(e) => {
+            log::warn!(
+                session = name;
+                "Unable to focus {}: {:#}",
+                name,
+                e
+            );
+            return false;
+        }
+    };
+
+    let mut tmux = Tmux::new(server);
+    tmux.command(["select-window", "-t", &id]);
+    if id.starts_with('%') {
+        tmux.command(["select-pane", "-t", &id]);
+    }
+    tmux.run(false).is_some()
+}
+
+pub fn create_session(name: &str, session: &Session) -> bool {
+    let mut tmux = Tmux::new(&session.server);
+    let window = target::window(name);
+    let mut containers = HashMap::new();
+    for (i, w) in session.windows.iter().enumerate() {
+        let dir = join_dir(&session.root, &w.dir);
+        let container = match w.container.as_str() {
+            "" => None,
+            c => match containers.get(c) {
+                Some(container) => Some(container),
+                None => match container::resolve(c, &session.root) {
+                    Ok(container) => Some(&*containers.entry(c).or_insert(container)),
+                    Err(e) => {
+                        log::error!(
+                            session = name;
+                            "Failed to find container {} of {}: {:#}",
+                            c,
+                            name,
+                            e
+                        );
+                        return false;
+                    }
+                },
+            },
+        };
+        // commands are run in container with the same environment
+        let wrap = |command: &String, dir: &Path| match container {
+            Some(c) => {
+                let env = session.env.iter().chain(w.env.iter());
+                let env = env.map(|(k, v)| (k.clone(), v.clone())).collect();
+                container::wrap(c, command, dir, &env)
+            }
+            None => command.clone(),
+        };
+        // session environment is set once session exists, so windows get it explicitly
+        let env: Vec<String> = session
+            .env
+            .iter()
+            .chain(w.env.iter())
+            .flat_map(|(k, v)| ["-e".to_string(), format!("{}={}", k, v)])
+            .collect();
+        let mut command = match i {
+            0 => vec!["new-session", "-d", "-s", name],
+            _ => vec!["new-window", "-t", &window],
+        };
+        command.extend(["-c", dir.to_str().unwrap(), "-n", &w.name]);
+        command.extend(env.iter().map(|s| s.as_str()));
+        let window_command = wrap(&w.command, &w.dir);
+        command.push(&window_command);
+        tmux.command(command);
+
+        // new window is current one
+        for (k, v) in w.options.iter() {
+            tmux.command(["set-option", "-w", "-t", &window, k, v]);
+        }
+        for pane in w.pane.iter() {
+            let dir = join_dir(&dir, &pane.dir);
+            let mut command = vec!["split-window", "-t", &window, "-c", dir.to_str().unwrap()];
+            command.extend(env.iter().map(|s| s.as_str()));
+            let pane_command = wrap(&pane.command, &join_dir(&w.dir, &pane.dir));
+            command.push(&pane_command);
+            tmux.command(command);
+        }
+        if !w.layout.is_empty() {
+            tmux.command(["select-layout", "-t", &window, &w.layout]);
+        }
+    }
+    for (k, v) in session.options.iter() {
+        tmux.command(["set-option", "-t", &window, k, v]);
+    }
+    // windows created later by user inherit it from session
+    for (k, v) in session.env.iter() {
+        tmux.command(["set-environment", "-t", &target::session(name), k, v]);
+    }
+    for command in session.tmux_commands.iter() {
+        match shell_words::split(command) {
+            Ok(args) => tmux.command(args),
+            Err(e) => {
+                log::error!("Invalid tmux command {:?}: {}", command, e);
+                return false;
+            }
+        }
+    }
+    if tmux.run(false).is_none() {
+        return false;
+    }
+
+    // last created window is current, so first one is focused by default
+    let first = Focus {
+        window: "0".to_string(),
+        pane: None,
+        always: false,
+    };
+    focus(
+        name,
+        &session.server,
+        session.focus.as_ref().unwrap_or(&first),
+    );
+    true
+}
+
+/// Joins relative directory, empty one means base itself
+fn join_dir(base: &Path, dir: &Path) -> PathBuf {
+    match dir.as_os_str().is_empty() {
+        true => base.to_path_buf(),
+        false => base.join(dir),
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::config::Window;
+    use std::collections::BTreeMap;
+
+    /// Private tmux server, killed on drop
+    pub struct TestServer(pub Server);
+
+    impl TestServer {
+        /// Returns None if tmux is not installed
+        pub fn new(name: &str) -> Option<Self> {
+            Command::new("tmux").arg("-V").output().ok()?;
+            let server = Server {
+                socket_name: Some(format!("tiramisu-test-{}-{}", std::process::id(), name)),
+                socket_path: None,
+            };
+            let mut tmux = Tmux::new(&server);
+            tmux.command(["-f", "/dev/null", "new-session", "-d", "-s", "main"]);
+            tmux.run(false)?;
+            Some(Self(server))
+        }
+
+        pub fn run(&self, args: &[&str]) -> String {
+            let mut tmux = Tmux::new(&self.0).quiet();
+            tmux.command(args);
+            tmux.run(false).unwrap_or_default()
+        }
+
+        pub fn session(&self, windows: &[&str], state: State) -> Session {
+            Session {
+                state,
+                server: self.0.clone(),
+                root: std::env::temp_dir(),
+                env: BTreeMap::new(),
+                options: BTreeMap::new(),
+                tmux_commands: Vec::new(),
+                focus: None,
+                view: false,
+                detach: false,
+                read_only: false,
+                windows: windows
+                    .iter()
+                    .map(|name| Window {
+                        name: name.to_string(),
+                        command: String::new(),
+                        dir: PathBuf::new(),
+                        env: BTreeMap::new(),
+                        options: BTreeMap::new(),
+                        kill: Vec::new(),
+                        container: String::new(),
+                        layout: String::new(),
+                        pane: Vec::new(),
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl Drop for TestServer {
+        fn drop(&mut self) {
+            self.run(&["kill-server"]);
+        }
+    }
+
+    #[test]
+    fn test_exact_targets() {
+        let Some(server) = TestServer::new("exact") else {
+            return;
+        };
+        for name in ["foobar", "=app", "1"] {
+            assert!(create_session(name, &server.session(&["a"], State::None)));
+        }
+        let sessions = server.run(&["ls", "-F", "#{session_name}"]);
+        assert_eq!(
+            sessions.lines().collect::<Vec<_>>(),
+            ["1", "=app", "foobar", "main"]
+        );
+
+        // prefix of other session is not matched
+        assert!(!kill("foo", &server.session(&["a"], State::Created)));
+        assert!(kill("=app", &server.session(&["a"], State::Created)));
+        assert!(kill("1", &server.session(&["a"], State::Created)));
+        let sessions = server.run(&["ls", "-F", "#{session_name}"]);
+        assert_eq!(sessions.lines().collect::<Vec<_>>(), ["foobar", "main"]);
+    }
+
+    #[test]
+    fn test_window_ids() {
+        let Some(server) = TestServer::new("windows") else {
+            return;
+        };
+        server.run(&["set-option", "-g", "base-index", "1"]);
+        let session = server.session(&["a", "b", "c"], State::None);
+        assert!(create_session("s", &session));
+        let windows = server.run(&["list-windows", "-t", "=s", "-F", "#I #W"]);
+        assert_eq!(windows.lines().collect::<Vec<_>>(), ["1 a", "2 b", "3 c"]);
+
+        assert!(kill("s", &server.session(&["a", "b", "c"], State::Created)));
+        assert_eq!(server.run(&["ls", "-F", "#{session_name}"]), "main\n");
+    }
+
+    #[test]
+    fn test_list() {
+        let Some(server) = TestServer::new("list") else {
+            return;
+        };
+        let session = server.session(&["a b", "c"], State::None);
+        assert!(create_session("name with spaces", &session));
+
+        let sessions = list_sessions(&server.0).unwrap();
+        let names: Vec<_> = sessions
+            .iter()
+            .map(|s| (s.name.as_str(), s.windows))
+            .collect();
+        assert_eq!(names, [("main", 1), ("name with spaces", 2)]);
+        let windows = list_windows("name with spaces", &server.0).unwrap();
+        let names: Vec<_> = windows.iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(names, ["a b", "c"]);
+        assert!(list_windows("missing", &server.0).is_err());
+
+        // server, which is not running, has no sessions
+        let server = Server {
+            socket_name: Some(format!("tiramisu-test-{}-none", std::process::id())),
+            socket_path: None,
+        };
+        assert!(list_sessions(&server).unwrap().is_empty());
+        assert!(list_windows("main", &server).is_err());
+    }
+
+    #[test]
+    fn test_dir_and_env() {
+        let Some(server) = TestServer::new("env") else {
+            return;
+        };
+        let root = std::env::temp_dir().join(format!("tiramisu-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("sub dir")).unwrap();
+        let mut session = server.session(&["a", "b"], State::None);
+        session.root = root.clone();
+        session.env = BTreeMap::from([("A".into(), "session".into())]);
+        session.windows[1].dir = "sub dir".into();
+        session.windows[1].env = BTreeMap::from([("B".into(), "x y".into())]);
+        assert!(create_session("s", &session));
+
+        let panes = server.run(&["list-panes", "-s", "-t", "=s", "-F", "#{pane_start_path}"]);
+        let expected = [root.clone(), root.join("sub dir")].map(|p| format!("{}\n", p.display()));
+        assert_eq!(panes, expected.concat());
+        let env = server.run(&["show-environment", "-t", "=s", "A"]);
+        assert_eq!(env, "A=session\n");
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_options() {
+        let Some(server) = TestServer::new("options") else {
+            return;
+        };
+        let mut session = server.session(&["a", "b"], State::None);
+        session.options = BTreeMap::from([("status-style".into(), "bg=red".into())]);
+        session.windows[0].options = BTreeMap::from([("remain-on-exit".into(), "on".into())]);
+        session.tmux_commands = vec!["set-option -t =s: @note 'a b'".into()];
+        assert!(create_session("s", &session));
+
+        let options = server.run(&[
+            "list-windows",
+            "-t",
+            "=s",
+            "-F",
+            "#{remain-on-exit} #{status-style} #{@note}",
+        ]);
+        assert_eq!(options, "on bg=red a b\noff bg=red a b\n");
+        assert_eq!(server.run(&["show", "-t", "=main:", "status-style"]), "");
+
+        session.tmux_commands = vec!["set-option 'unterminated".into()];
+        assert!(!create_session("t", &session));
+    }
+
+    #[test]
+    fn test_focus() {
+        let Some(server) = TestServer::new("focus") else {
+            return;
+        };
+        let current = || server.run(&["display", "-p", "-t", "=s:", "#W #P"]);
+        let mut session = server.session(&["a", "b", "c"], State::None);
+        assert!(create_session("s", &session));
+        assert_eq!(current(), "a 0\n");
+
+        server.run(&["split-window", "-t", "=s:", "-d"]);
+        let mut f = Focus {
+            window: "c".into(),
+            pane: None,
+            always: true,
+        };
+        assert!(focus("s", &server.0, &f));
+        assert_eq!(current(), "c 0\n");
+        (f.window, f.pane) = ("0".into(), Some(1));
+        assert!(focus("s", &server.0, &f));
+        assert_eq!(current(), "a 1\n");
+        (f.window, f.pane) = ("missing".into(), None);
+        assert!(!focus("s", &server.0, &f));
+
+        session.focus = Some(Focus {
+            window: "b".into(),
+            pane: None,
+            always: false,
+        });
+        assert!(create_session("t", &session));
+        let current = server.run(&["display", "-p", "-t", "=t:", "#W"]);
+        assert_eq!(current, "b\n");
+    }
+
+    #[test]
+    fn test_views() {
+        let Some(server) = TestServer::new("views") else {
+            return;
+        };
+        let session = server.session(&["a", "b"], State::Created);
+        assert!(create_session("s", &session));
+        assert_eq!(create_view("s", &server.0).unwrap(), "s+1");
+        assert_eq!(create_view("s", &server.0).unwrap(), "s+2");
+        assert!(create_view("missing", &server.0).is_none());
+
+        let sessions = list_sessions(&server.0).unwrap();
+        let views: Vec<_> = sessions.iter().map(|s| (&*s.name, &*s.view)).collect();
+        assert_eq!(views, [("main", ""), ("s", ""), ("s+1", "s"), ("s+2", "s")]);
+        assert!(
+            sessions[1..]
+                .iter()
+                .all(|s| s.group == "s" && s.windows == 2)
+        );
+
+        // view is not left behind, when it fails to be opened
+        let viewed = Session {
+            view: true,
+            state: State::Created,
+            ..session.clone()
+        };
+        assert!(!open("s", &viewed, &Mode::Terminal(String::new())));
+        assert_eq!(list_sessions(&server.0).unwrap().len(), 4);
+
+        // views are killed along with session they view
+        assert!(kill("s", &session));
+        assert_eq!(list_sessions(&server.0).unwrap().len(), 1);
+    }
+
+    /// Attaches control mode client, which stays attached until it is dropped
+    fn attach_control(server: &Server, name: &str) -> std::process::Child {
+        use std::io::BufRead;
+        let mut client = Command::new("tmux")
+            .args(server.args())
+            .args(["-C", "attach", "-t", &target::session(name)])
+            .env_remove("TMUX")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // reply to attach command is sent once client is attached
+        let mut stdout = std::io::BufReader::new(client.stdout.take().unwrap());
+        let mut line = String::new();
+        while !line.starts_with("%end") {
+            line.clear();
+            assert_ne!(stdout.read_line(&mut line).unwrap(), 0);
+        }
+        // closed output would make client exit
+        client.stdout = Some(stdout.into_inner());
+        client
+    }
+
+    #[test]
+    fn test_detach_others() {
+        let Some(server) = TestServer::new("detach") else {
+            return;
+        };
+        let session = Session {
+            detach: true,
+            read_only: true,
+            ..server.session(&["a"], State::None)
+        };
+        assert_eq!(attach_flags(&session), ["-d", "-r"]);
+        assert!(attach_flags(&server.session(&["a"], State::None)).is_empty());
+
+        assert!(create_session("s", &session));
+        let clients = [
+            attach_control(&server.0, "s"),
+            attach_control(&server.0, "s"),
+        ];
+        // outside of tmux, most recently used client is the current one
+        assert!(detach_others("s", &server.0));
+        let attached = server.run(&["list-clients", "-F", "#{client_name}"]);
+        assert_eq!(attached.lines().count(), 1);
+        // session without clients has nothing to detach
+        assert!(create_session("t", &session));
+        assert!(detach_others("t", &server.0));
+        assert!(!detach_others("missing", &server.0));
+        drop(clients);
+    }
+
+    #[test]
+    fn test_attach_command() {
+        let server = Server {
+            socket_name: Some("work".into()),
+            socket_path: None,
+        };
+        let attach = shell_words::join(attach_command("a b", &server, false, &["-d"]));
+        assert_eq!(attach, "env -u TMUX tmux -L work attach -d -t '=a b'");
+        let attach = shell_words::join(attach_command("s+1", &Server::default(), true, &[]));
+        assert_eq!(
+            attach,
+            "env -u TMUX tmux attach -t '=s+1' ';' set-option -t '=s+1:' destroy-unattached on"
+        );
+    }
+
+    #[test]
+    fn test_terminal() {
+        let path = std::env::temp_dir().join(format!("tiramisu-terminal-{}", std::process::id()));
+        let command = format!("echo {{}} > {}", path.display());
+        assert!(!terminal("", "s", &Server::default(), false, &[]));
+        let attach = shell_words::join(attach_command("s", &Server::default(), false, &[]));
+        let mut child = terminal_command(&command, &attach).spawn().unwrap();
+        assert!(child.wait().unwrap().success());
+        let output = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(output, "env -u TMUX tmux attach -t =s\n");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+//! Builds tmux targets, so that session names are never interpreted as target syntax
+
+use anyhow::bail;
+
+/// Targets session by exact name, without prefix or pattern matching
+/// Valid only for commands taking session, use `window` for windows and panes
+pub fn session(name: &str) -> String {
+    format!("={}", name)
+}
+
+/// Targets current window of session, for `new-window` it is next free index instead
+pub fn window(name: &str) -> String {
+    format!("={}:", name)
+}
+
+/// Checks session name, returns name tmux would keep as is
+/// `.` and `:` are separators in targets, tmux replaces them, other characters are protected
+/// by `=` prefix of targets
+pub fn sanitize(name: &str) -> anyhow::Result<String> {
+    if name.is_empty() {
+        bail!("session name is empty");
+    }
+    Ok(name.replace(['.', ':'], "_"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_targets() {
+        assert_eq!(session("src/app"), "=src/app");
+        assert_eq!(session("$1"), "=$1");
+        assert_eq!(window("src/app"), "=src/app:");
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("src/app").unwrap(), "src/app");
+        assert_eq!(sanitize("app.rs").unwrap(), "app_rs");
+        assert_eq!(sanitize("host:22").unwrap(), "host_22");
+        assert_eq!(sanitize("$HOME").unwrap(), "$HOME");
+        assert_eq!(sanitize("a\\b").unwrap(), "a\\b");
+        assert_eq!(sanitize("a b'\"").unwrap(), "a b'\"");
+        assert_eq!(sanitize("=app").unwrap(), "=app");
+        assert!(sanitize("").is_err());
+    }
+}
@@ -1,17 +1,26 @@
 use std::borrow::Cow;
 
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseEventKind};
 use ratatui::{
     layout::{Constraint, Layout, Margin, Offset, Rect},
     style::{Color, Style},
     symbols::border,
-    text::Span,
-    widgets::{Block, Widget},
+    text::{Line, Span},
+    widgets::{Block, StatefulWidget, Widget},
 };
 
+/// Descriptions are wrapped to fit screen, but not narrower than this
+const MIN_HELP_WIDTH: u16 = 16;
+
+/// Scroll position of help, which may not fit screen
+pub struct HelpView {
+    /// Number of lines scrolled down from the first one
+    scroll: usize,
+}
+
 pub struct HelpWidget<'a> {
     key_width: u16,
     width: u16,
-    height: u16,
     help: Vec<Help<'a>>,
     background: Block<'a>,
 }
@@ -30,65 +39,252 @@ impl<'a> Help<'a> {
     }
 }
 
-impl<'a> HelpWidget<'a> {
+impl HelpView {
     pub fn new() -> Self {
-        let mut v: Vec<Help<'a>> = Vec::new();
-        v.push(Help::new("ctrl+?/ctrl+7", "toggle help"));
-        v.push(Help::new("ctrl+c/esc", "quit"));
-        v.push(Help::new("enter", "switch to selected session"));
-        v.push(Help::new("ctrl+x", "kill selected session"));
-        v.push(Help::new("ctrl+p/up ctrl+n/down", "move selection"));
-        v.push(Help::new("", ""));
-        v.push(Help::new("ctrl+h/backspace", "backspace"));
-        v.push(Help::new("ctrl+d/delete", "delete"));
-        v.push(Help::new("ctrl+b/left, right", "move prompt cursor"));
-        v.push(Help::new("ctrl+a", "move prompt cursor to beginning"));
-        v.push(Help::new("ctrl+e", "move prompt cursor to end"));
-        v.push(Help::new("ctrl+w", "delete from cursor to beginning"));
-        v.push(Help::new("ctrl+k", "delete from cursor to end"));
-        v.push(Help::new("ctrl+shift+v/cmd+v", "paste"));
+        Self { scroll: 0 }
+    }
+
+    pub fn handle_event(&mut self, evt: &Event) -> bool {
+        if let Event::Mouse(event) = evt {
+            match event.kind {
+                MouseEventKind::ScrollUp => self.scroll = self.scroll.saturating_sub(1),
+                MouseEventKind::ScrollDown => self.scroll += 1,
+                _ => return false,
+            }
+            return true;
+        }
+        let Some(event) = evt.as_key_press_event() else {
+            return false;
+        };
+        match (event.code, event.modifiers) {
+            (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                self.scroll = self.scroll.saturating_sub(1)
+            }
+            (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => self.scroll += 1,
+            (KeyCode::PageUp, _) => self.scroll = self.scroll.saturating_sub(10),
+            (KeyCode::PageDown, _) => self.scroll += 10,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl<'a> HelpWidget<'a> {
+    pub fn new(border: border::Set<'a>) -> Self {
+        let v: Vec<Help<'a>> = vec![
+            Help::new("ctrl+?/ctrl+7", "toggle help"),
+            Help::new("ctrl+c/esc", "quit"),
+            Help::new("enter", "switch to selected session, create marked ones"),
+            Help::new("alt+v", "open grouped view of selected session"),
+            Help::new(
+                "alt+o",
+                "switch to selected session, detach its other clients",
+            ),
+            Help::new("alt+r", "open selected session read-only"),
+            Help::new("alt+l", "link window of selected session to current one"),
+            Help::new("alt+p", "open selected session in popup"),
+            Help::new("alt+t", "open selected session in new terminal"),
+            Help::new("ctrl+x", "kill selected or marked sessions"),
+            Help::new("ctrl+p/up ctrl+n/down", "move selection"),
+            Help::new("tab/shift+tab", "toggle mark and move selection"),
+            Help::new("alt+a", "toggle mark on all matches"),
+            Help::new(
+                "ctrl+l",
+                "toggle log view, tab cycles level, typing searches",
+            ),
+            Help::new("ctrl+s", "save snapshot of running sessions"),
+            Help::new("ctrl+r", "restore sessions from snapshot"),
+            Help::new("alt+e", "append selected running session to config"),
+            Help::new("", ""),
+            Help::new("ctrl+h/backspace", "backspace"),
+            Help::new("ctrl+d/delete", "delete"),
+            Help::new("ctrl+b/left, right", "move prompt cursor"),
+            Help::new("ctrl+a", "move prompt cursor to beginning"),
+            Help::new("ctrl+e", "move prompt cursor to end"),
+            Help::new("ctrl+w", "delete from cursor to beginning"),
+            Help::new("ctrl+k", "delete from cursor to end"),
+            Help::new("ctrl+shift+v/cmd+v", "paste"),
+        ];
         Self {
             key_width: v.iter().map(|s| s.key.width()).max().unwrap() as u16 + 2,
             width: v.iter().map(|s| s.help.width()).max().unwrap() as u16,
-            height: v.len() as u16,
             help: v,
             background: Block::bordered()
                 .style(Style::default().bg(Color::Black).fg(Color::Black))
-                .border_set(border::ROUNDED)
+                .border_set(border)
                 .border_style(Color::DarkGray),
         }
     }
 }
 
-impl<'a> Widget for HelpWidget<'a> {
-    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer) {
-        if area.width < self.width + self.key_width + 6 || area.height < self.height + 2 {
-            log::error!("not enough screen space to render help");
-            return;
+impl<'a> HelpWidget<'a> {
+    /// Returns area of help box including its border, None if screen is too small
+    pub fn area(&self, area: Rect) -> Option<Rect> {
+        self.layout(area)
+            .map(|(inner, _)| inner.outer(Margin::new(2, 1)))
+    }
+
+    /// Returns area of help text and width of descriptions, which are wrapped to fit screen
+    /// Help is scrolled, if it is higher than screen
+    fn layout(&self, area: Rect) -> Option<(Rect, u16)> {
+        let width = self
+            .width
+            .min(area.width.saturating_sub(self.key_width + 6));
+        if width < MIN_HELP_WIDTH || area.height < 3 {
+            return None;
         }
+        let height = (rows(&self.help, width).len() as u16).min(area.height - 2);
+
         let [_, vl, _] = Layout::vertical([
             Constraint::Fill(1),
-            Constraint::Length(self.height),
+            Constraint::Length(height),
             Constraint::Fill(1),
         ])
         .areas(area);
 
         let [_, hl, _] = Layout::horizontal([
             Constraint::Fill(1),
-            Constraint::Length(self.width + self.key_width + 2),
+            Constraint::Length(width + self.key_width + 2),
             Constraint::Fill(1),
         ])
         .areas(area);
 
-        let area = Rect::new(hl.x, vl.y, hl.width, vl.height);
+        Some((Rect::new(hl.x, vl.y, hl.width, vl.height), width))
+    }
+}
+
+impl<'a> StatefulWidget for HelpWidget<'a> {
+    type State = HelpView;
+
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer, state: &mut HelpView) {
+        let Some((area, width)) = self.layout(area) else {
+            log::error!("not enough screen space to render help");
+            return;
+        };
+        let rows = rows(&self.help, width);
 
-        self.background.render(area.outer(Margin::new(2, 1)), buf);
-        for (y, help) in self.help.into_iter().enumerate() {
-            help.key.render(area.offset(Offset::new(0, y as i32)), buf);
-            help.help.render(
+        // position is shown, when help does not fit
+        let height = area.height as usize;
+        state.scroll = state.scroll.min(rows.len() - height);
+        let mut background = self.background;
+        if rows.len() > height {
+            let position = format!(
+                " {}-{}/{} ",
+                state.scroll + 1,
+                state.scroll + height,
+                rows.len()
+            );
+            background =
+                background.title_bottom(Line::styled(position, Color::DarkGray).right_aligned());
+        }
+        background.render(area.outer(Margin::new(2, 1)), buf);
+
+        for (y, (key, help)) in rows.into_iter().skip(state.scroll).take(height).enumerate() {
+            if let Some(key) = key {
+                key.clone()
+                    .render(area.offset(Offset::new(0, y as i32)), buf);
+            }
+            help.render(
                 area.offset(Offset::new(self.key_width as i32 + 2, y as i32)),
                 buf,
             );
         }
     }
 }
+
+/// Splits help into lines, descriptions are wrapped at given width
+/// Key is shown on the first line of its description
+fn rows<'a, 'b>(help: &'b [Help<'a>], width: u16) -> Vec<(Option<&'b Span<'a>>, Span<'a>)> {
+    let mut rows = Vec::new();
+    for h in help {
+        for (i, line) in wrap(&h.help.content, width as usize)
+            .into_iter()
+            .enumerate()
+        {
+            let key = (i == 0).then_some(&h.key);
+            rows.push((key, Span::styled(line, h.help.style)));
+        }
+    }
+    rows
+}
+
+/// Wraps text by words, so that lines are not longer than width if words are not
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in text.split(' ') {
+        let line = lines.last_mut().unwrap();
+        if line.is_empty() {
+            line.push_str(word);
+        } else if line.chars().count() + 1 + word.chars().count() <= width {
+            line.push(' ');
+            line.push_str(word);
+        } else {
+            lines.push(word.to_string());
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::event::KeyEvent;
+    use ratatui::buffer::Buffer;
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn render(area: Rect, view: &mut HelpView) -> Vec<String> {
+        let mut buf = Buffer::empty(area);
+        HelpWidget::new(border::PLAIN).render(area, &mut buf, view);
+        (0..area.height)
+            .map(|y| {
+                let line: String = (0..area.width).map(|x| buf[(x, y)].symbol()).collect();
+                line.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("", 5), [""]);
+        assert_eq!(wrap("a bb ccc dddddd", 4), ["a bb", "ccc", "dddddd"]);
+    }
+
+    #[test]
+    fn test_popup() {
+        // `display-popup -w 70% -h 70%` of 80x24 terminal, without popup border
+        let area = Rect::new(0, 0, 54, 14);
+        let mut view = HelpView::new();
+        let help = HelpWidget::new(border::PLAIN);
+        assert_eq!(help.area(area), Some(area));
+        let total = rows(&help.help, 54 - help.key_width - 6).len();
+        assert!(total > 12);
+
+        let lines = render(area, &mut view);
+        assert!(lines[1].contains("ctrl+?/ctrl+7") && lines[1].contains("toggle help"));
+        assert!(lines[13].ends_with(&format!(" 1-12/{} ┘", total)));
+        // long descriptions are wrapped instead of cut
+        assert!(lines[8].contains("alt+o") && lines[8].contains("switch to selected"));
+        assert!(lines[9].contains("session, detach its other"));
+        assert!(lines[10].contains("clients"));
+
+        // scroll stops at the last line
+        for _ in 0..5 {
+            assert!(view.handle_event(&key(KeyCode::PageDown)));
+        }
+        let lines = render(area, &mut view);
+        assert!(lines[12].contains("ctrl+shift+v/cmd+v") && lines[12].contains("paste"));
+        assert!(lines[13].ends_with(&format!(" {}-{}/{} ┘", total - 11, total, total)));
+        assert!(view.handle_event(&key(KeyCode::Up)));
+        assert!(!render(area, &mut view)[12].contains("paste"));
+
+        // help, which fits screen, is not scrolled
+        let area = Rect::new(0, 0, 100, 40);
+        let lines = render(area, &mut view);
+        assert!(lines.iter().any(|l| l.contains("toggle help")));
+        assert!(lines.iter().any(|l| l.contains("paste")));
+        assert!(!lines.iter().any(|l| l.contains('/') && l.contains('┘')));
+    }
+}
@@ -0,0 +1,269 @@
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseEventKind};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, StatefulWidget, Widget},
+};
+
+use super::{Message, Prompt};
+
+/// Levels `tab` cycles through, from the most verbose one
+const LEVELS: [log::LevelFilter; 5] = [
+    log::LevelFilter::Trace,
+    log::LevelFilter::Debug,
+    log::LevelFilter::Info,
+    log::LevelFilter::Warn,
+    log::LevelFilter::Error,
+];
+
+/// Scrollable view of messages logged since start, filtered by level and search
+pub struct LogView {
+    prompt: Prompt,
+    level: log::LevelFilter,
+    /// Number of lines scrolled up from the newest message
+    scroll: usize,
+    /// Messages, which pass filters, they are kept until filters change
+    matched: Vec<Message>,
+    /// Number of logged messages filtered so far
+    seen: usize,
+}
+
+pub struct LogViewWidget<'a> {
+    border: border::Set<'a>,
+}
+
+impl LogView {
+    pub fn new() -> Self {
+        Self {
+            prompt: Prompt::new(),
+            level: log::LevelFilter::Trace,
+            scroll: 0,
+            matched: Vec::new(),
+            seen: 0,
+        }
+    }
+
+    pub fn prompt(&self) -> &Prompt {
+        &self.prompt
+    }
+
+    pub fn level(&self) -> log::LevelFilter {
+        self.level
+    }
+
+    /// Returns number of messages, which pass filters
+    pub fn matched_len(&self) -> usize {
+        self.matched.len()
+    }
+
+    /// Returns number of all messages
+    pub fn len(&self) -> usize {
+        self.seen
+    }
+
+    /// Filters messages logged since last update, `history` holds all of them
+    /// Messages, which pass level filter and contain search query (case insensitive), are kept
+    pub fn update(&mut self, history: &[Message]) {
+        let query = self.prompt.value().to_lowercase();
+        let new = history.get(self.seen..).unwrap_or_default();
+        self.matched.extend(
+            new.iter()
+                .filter(|m| m.level() <= self.level)
+                .filter(|m| m.text().to_lowercase().contains(&query))
+                .cloned(),
+        );
+        self.seen = history.len();
+    }
+
+    /// Forgets filtered messages, they are filtered again on next update
+    fn reset(&mut self) {
+        self.scroll = 0;
+        self.matched.clear();
+        self.seen = 0;
+    }
+
+    pub fn handle_event(&mut self, evt: &Event) -> bool {
+        if self.prompt.handle_event(evt).value {
+            self.reset();
+            return true;
+        }
+        if let Event::Mouse(event) = evt {
+            match event.kind {
+                MouseEventKind::ScrollUp => self.scroll += 1,
+                MouseEventKind::ScrollDown => self.scroll = self.scroll.saturating_sub(1),
+                _ => return false,
+            }
+            return true;
+        }
+        let Some(event) = evt.as_key_press_event() else {
+            return false;
+        };
+        match (event.code, event.modifiers) {
+            (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => self.scroll += 1,
+            (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                self.scroll = self.scroll.saturating_sub(1)
+            }
+            (KeyCode::PageUp, _) => self.scroll += 10,
+            (KeyCode::PageDown, _) => self.scroll = self.scroll.saturating_sub(10),
+            (KeyCode::Tab, _) => {
+                let i = LEVELS.iter().position(|l| *l == self.level).unwrap_or(0);
+                self.level = LEVELS[(i + 1) % LEVELS.len()];
+                self.reset();
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl<'a> LogViewWidget<'a> {
+    pub fn new(border: border::Set<'a>) -> Self {
+        Self { border }
+    }
+}
+
+impl<'a> StatefulWidget for LogViewWidget<'a> {
+    type State = LogView;
+
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer, state: &mut LogView) {
+        let block = Block::bordered()
+            .title(" logs ")
+            .border_set(self.border)
+            .border_style(Color::DarkGray);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        // newest message is at the bottom, scroll stops at the oldest one
+        let height = inner.height as usize;
+        let messages = &state.matched;
+        state.scroll = state.scroll.min(messages.len().saturating_sub(height));
+        let end = messages.len() - state.scroll;
+        let start = end.saturating_sub(height);
+        let offset = height - (end - start);
+        for (i, m) in messages[start..end].iter().enumerate() {
+            let line = Line::from(vec![
+                Span::styled(m.time().format("%H:%M:%S ").to_string(), Color::DarkGray),
+                Span::styled(
+                    format!("{:<6}", m.level()),
+                    Style::from(level_color(m.level())),
+                ),
+                Span::styled(m.text().replace('\n', " "), Color::Gray),
+            ]);
+            let y = inner.y + (offset + i) as u16;
+            line.render(Rect::new(inner.x, y, inner.width, 1), buf);
+        }
+    }
+}
+
+fn level_color(level: log::Level) -> Color {
+    match level {
+        log::Level::Error => Color::Red,
+        log::Level::Warn => Color::Yellow,
+        log::Level::Info => Color::Blue,
+        log::Level::Debug | log::Level::Trace => Color::Gray,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::event::{KeyEvent, MouseEvent};
+    use ratatui::buffer::Buffer;
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn message(level: log::Level, text: &str) -> Message {
+        Message::new(level, text.to_string(), chrono::Local::now())
+    }
+
+    /// Renders view two lines high and returns texts of messages shown in it
+    fn render(view: &mut LogView) -> Vec<String> {
+        let area = Rect::new(0, 0, 40, 4);
+        let mut buf = Buffer::empty(area);
+        LogViewWidget::new(border::PLAIN).render(area, &mut buf, view);
+        (1..3)
+            .map(|y| (16..39).map(|x| buf[(x, y)].symbol()).collect::<String>())
+            .map(|line| line.trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut history = vec![
+            message(log::Level::Info, "Tmux started"),
+            message(log::Level::Debug, "tmux ls"),
+            message(log::Level::Error, "Failed to open"),
+        ];
+        let mut view = LogView::new();
+        view.update(&history);
+        assert_eq!((view.matched_len(), view.len()), (3, 3));
+
+        // levels are cycled from the most verbose one
+        let mut matched = Vec::new();
+        for _ in 0..LEVELS.len() {
+            assert!(view.handle_event(&key(KeyCode::Tab)));
+            view.update(&history);
+            matched.push((view.level(), view.matched_len()));
+        }
+        assert_eq!(
+            matched,
+            [
+                (log::LevelFilter::Debug, 3),
+                (log::LevelFilter::Info, 2),
+                (log::LevelFilter::Warn, 1),
+                (log::LevelFilter::Error, 1),
+                (log::LevelFilter::Trace, 3),
+            ]
+        );
+
+        // search is case insensitive, new messages are filtered as well
+        for c in "tmux".chars() {
+            assert!(view.handle_event(&key(KeyCode::Char(c))));
+        }
+        view.update(&history);
+        assert_eq!(view.matched_len(), 2);
+        history.push(message(log::Level::Warn, "tmux failed"));
+        history.push(message(log::Level::Warn, "other"));
+        view.update(&history);
+        assert_eq!((view.matched_len(), view.len()), (3, 5));
+    }
+
+    #[test]
+    fn test_scroll() {
+        let history: Vec<_> = (0..5)
+            .map(|i| message(log::Level::Info, &format!("m{}", i)))
+            .collect();
+        let mut view = LogView::new();
+        view.update(&history);
+        assert_eq!(render(&mut view), ["m3", "m4"]);
+
+        assert!(view.handle_event(&key(KeyCode::Up)));
+        assert_eq!(render(&mut view), ["m2", "m3"]);
+        // scroll stops at the oldest message
+        assert!(view.handle_event(&key(KeyCode::PageUp)));
+        assert_eq!(render(&mut view), ["m0", "m1"]);
+        assert!(view.handle_event(&key(KeyCode::Down)));
+        assert_eq!(render(&mut view), ["m1", "m2"]);
+        let scroll = Event::Mouse(MouseEvent {
+            kind: MouseEventKind::ScrollDown,
+            column: 0,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        });
+        assert!(view.handle_event(&scroll));
+        assert_eq!(render(&mut view), ["m2", "m3"]);
+
+        // search starts from the newest message
+        assert!(view.handle_event(&key(KeyCode::Char('m'))));
+        view.update(&history);
+        assert_eq!(render(&mut view), ["m3", "m4"]);
+
+        let mut view = LogView::new();
+        view.update(&history[..1]);
+        assert_eq!(render(&mut view), ["", "m0"]);
+    }
+}
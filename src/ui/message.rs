@@ -20,21 +20,30 @@ impl Message {
     pub fn time(&self) -> chrono::DateTime<chrono::Local> {
         self.time
     }
+
+    pub fn level(&self) -> log::Level {
+        self.level
+    }
+
+    pub fn text(&self) -> &str {
+        &self.message
+    }
 }
 
 impl<'a> MessageWidget<'a> {
     pub fn new<I: Iterator<Item = &'a Message>>(msg: I) -> Self {
         let mut data = Vec::new();
         for m in msg {
-            let mut line = Vec::new();
-            line.push(match m.level {
-                log::Level::Error => Span::styled("error: ", Color::Red),
-                log::Level::Warn => Span::styled("warn: ", Color::Yellow),
-                log::Level::Info => Span::styled("info: ", Color::Blue),
-                log::Level::Debug => Span::styled("debug: ", Color::Gray),
-                log::Level::Trace => Span::styled("trace: ", Color::Gray),
-            });
-            line.push(Span::styled(&m.message, Color::Gray));
+            let line = vec![
+                match m.level {
+                    log::Level::Error => Span::styled("error: ", Color::Red),
+                    log::Level::Warn => Span::styled("warn: ", Color::Yellow),
+                    log::Level::Info => Span::styled("info: ", Color::Blue),
+                    log::Level::Debug => Span::styled("debug: ", Color::Gray),
+                    log::Level::Trace => Span::styled("trace: ", Color::Gray),
+                },
+                Span::styled(&m.message, Color::Gray),
+            ];
 
             data.push(Line::default());
             data.push(Line::from(line));
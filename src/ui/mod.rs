@@ -1,12 +1,20 @@
 mod help;
+mod log_view;
 mod message;
 mod paragraph;
 mod prompt;
+mod query;
 mod session_list;
+mod theme;
 
 pub use paragraph::ParagraphBuilder;
 
-pub use help::HelpWidget;
+pub use help::{HelpView, HelpWidget};
+pub use log_view::{LogView, LogViewWidget};
 pub use message::{Message, MessageWidget};
 pub use prompt::{Prompt, PromtWidget};
-pub use session_list::{MatchedString, Session, SessionList, SessionListWidget, State};
+pub use query::Query;
+pub use session_list::{
+    MatchedString, Session, SessionInjector, SessionKey, SessionList, SessionListWidget, State,
+};
+pub use theme::Theme;
@@ -1,6 +1,7 @@
-use std::mem::replace;
+use std::mem::take;
 
 use ratatui::{
+    layout::{Position, Rect},
     text::{Line, Span, Text},
     widgets::Widget,
 };
@@ -59,7 +60,7 @@ impl<'a> ParagraphBuilder<'a> {
     }
 
     pub fn br(&mut self) {
-        self.data.push(replace(&mut self.last, Vec::new()).into());
+        self.data.push(take(&mut self.last).into());
     }
 
     pub fn rev(mut self) -> Self {
@@ -86,13 +87,13 @@ impl<'a> ParagraphBuilder<'a> {
 
 impl<'a> Widget for Paragraph<'a> {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
-        let i = usize::max(self.scroll, area.height as usize) - area.height as usize;
+        let first = first_line(self.scroll, area);
         let n = usize::min(self.content.len(), area.height as usize);
 
-        for (i, line) in self.content.iter().skip(i).take(n).enumerate() {
+        for (i, line) in self.content.iter().skip(first).take(n).enumerate() {
             let mut area = area;
             if self.rev {
-                area.y = area.height - 1 - i as u16;
+                area.y += area.height - 1 - i as u16;
             } else {
                 area.y += i as u16;
             }
@@ -100,3 +101,46 @@ impl<'a> Widget for Paragraph<'a> {
         }
     }
 }
+
+/// Returns index of line rendered at given row by paragraph of `len` lines,
+/// i.e. inverse of paragraph layout
+pub fn line_at(len: usize, scroll: usize, rev: bool, area: Rect, y: u16) -> Option<usize> {
+    if !area.contains(Position::new(area.x, y)) {
+        return None;
+    }
+    let row = (y - area.y) as usize;
+    let i = match rev {
+        true => area.height as usize - 1 - row,
+        false => row,
+    };
+    Some(first_line(scroll, area) + i).filter(|&i| i < len)
+}
+
+/// Index of the first visible line, lines are scrolled so that line `scroll` is just below area
+fn first_line(scroll: usize, area: Rect) -> usize {
+    usize::max(scroll, area.height as usize) - area.height as usize
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::buffer::Buffer;
+
+    use super::*;
+
+    #[test]
+    fn test_line_at() {
+        let lines = ["0", "1", "2", "3", "4"];
+        let area = Rect::new(2, 1, 3, 3);
+        for (rev, scroll) in [(false, 0), (false, 4), (true, 2), (true, 5)] {
+            let mut buf = Buffer::empty(Rect::new(0, 0, 6, 5));
+            Paragraph::from(lines.map(Line::from).to_vec())
+                .scroll(scroll)
+                .rev(rev)
+                .render(area, &mut buf);
+            for y in 0..5 {
+                let rendered = buf[(2, y)].symbol().trim().parse::<usize>().ok();
+                assert_eq!(line_at(lines.len(), scroll, rev, area, y), rendered);
+            }
+        }
+    }
+}
@@ -12,6 +12,7 @@ pub struct Prompt {
 
 pub struct PromtWidget<'a> {
     ln: Line<'a>,
+    rule: &'a str,
 }
 
 impl Prompt {
@@ -38,17 +39,20 @@ impl Prompt {
 }
 
 impl<'a> PromtWidget<'a> {
-    pub fn new(prompt: &'a Prompt, hint: &'a str) -> Self {
-        let mut data = Vec::new();
-        data.push("> ".to_span().blue().bold());
-        data.push(Span::raw(prompt.inner.value()));
-        data.push(" ".to_span());
-        data.push(" <".to_span().blue().bold());
-        data.push(" ".to_span());
-        data.push(Span::raw(hint).yellow());
+    /// Prompt is followed by hint and `rule` filling the rest of line
+    pub fn new(prompt: &'a Prompt, hint: &'a str, rule: &'a str) -> Self {
+        let data = vec![
+            "> ".to_span().blue().bold(),
+            Span::raw(prompt.inner.value()),
+            " ".to_span(),
+            " <".to_span().blue().bold(),
+            " ".to_span(),
+            Span::raw(hint).yellow(),
+        ];
 
         Self {
             ln: Line::from(data),
+            rule,
         }
     }
 }
@@ -56,7 +60,7 @@ impl<'a> PromtWidget<'a> {
 impl<'a> Widget for PromtWidget<'a> {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
         if area.width > self.ln.width() as u16 + 4 {
-            "─"
+            self.rule
                 .repeat(area.width as usize - self.ln.width() - 4)
                 .to_span()
                 .dark_gray()
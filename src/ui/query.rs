@@ -0,0 +1,233 @@
+use std::path::PathBuf;
+
+use crate::{config::Session, ui::State};
+
+/// Parsed search query
+/// Plain terms use fzf syntax (`^prefix`, `suffix$`, `'exact`, `!negation`) and are matched
+/// against searchable session fields, `field:value` terms filter sessions by metadata
+#[derive(Debug)]
+pub struct Query {
+    text: String,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug)]
+struct Filter {
+    field: Field,
+    value: String,
+    negate: bool,
+}
+
+#[derive(Debug)]
+enum Field {
+    State,
+    Root,
+    Tag,
+    Server,
+    Window,
+}
+
+impl Query {
+    /// Parses query, terms are separated by spaces (use `\ ` to match space itself)
+    pub fn parse(query: &str) -> Self {
+        let mut terms = Vec::new();
+        let mut filters = Vec::new();
+        for term in split(query) {
+            match Filter::parse(term) {
+                Some(filter) => filters.push(filter),
+                None => terms.push(term),
+            }
+        }
+
+        Self {
+            text: terms.join(" "),
+            filters,
+        }
+    }
+
+    /// Returns fuzzy part of query, which should be passed to nucleo pattern parser
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns window session should be opened at, if query has `window:` filter
+    /// Name of first matching window is returned
+    pub fn window<'a>(&self, session: &'a Session) -> Option<&'a str> {
+        let filter = self
+            .filters
+            .iter()
+            .find(|f| matches!(f.field, Field::Window) && !f.negate)?;
+        session
+            .window
+            .iter()
+            .find(|w| w.name.to_lowercase().contains(&filter.value))
+            .map(|w| w.name.as_str())
+    }
+
+    /// Checks whether session passes all field filters
+    pub fn filter(&self, session: &Session, state: &State) -> bool {
+        self.filters.iter().all(|f| f.matches(session, state))
+    }
+}
+
+impl Filter {
+    fn parse(term: &str) -> Option<Self> {
+        let (negate, term) = match term.strip_prefix('!') {
+            Some(term) => (true, term),
+            None => (false, term),
+        };
+        let (field, value) = term.split_once(':')?;
+        let field = match field {
+            "state" => Field::State,
+            "root" => Field::Root,
+            "tag" => Field::Tag,
+            "server" => Field::Server,
+            "window" => Field::Window,
+            _ => return None,
+        };
+        if value.is_empty() {
+            return None;
+        }
+
+        let value = value.replace("\\ ", " ");
+        let value = match (&field, value.strip_prefix('~')) {
+            (Field::Root, Some(rest)) => {
+                dirs::home_dir()
+                    .unwrap_or(PathBuf::from("~"))
+                    .to_string_lossy()
+                    .to_string()
+                    + rest
+            }
+            _ => value,
+        };
+
+        Some(Self {
+            field,
+            value: value.to_lowercase(),
+            negate,
+        })
+    }
+
+    fn matches(&self, session: &Session, state: &State) -> bool {
+        let matched = match self.field {
+            Field::State => match state {
+                State::None => "none",
+                State::Created => "created",
+                State::Attached => "attached",
+            }
+            .starts_with(&self.value),
+            Field::Root => session
+                .root
+                .to_string_lossy()
+                .to_lowercase()
+                .contains(&self.value),
+            Field::Tag => session.tags.iter().any(|t| t.to_lowercase() == self.value),
+            Field::Server => session
+                .server
+                .to_string()
+                .to_lowercase()
+                .contains(&self.value),
+            Field::Window => session
+                .window
+                .iter()
+                .any(|w| w.name.to_lowercase().contains(&self.value)),
+        };
+        matched != self.negate
+    }
+}
+
+/// Splits query by whitespace, which is not escaped with `\`
+/// Escapes are kept in terms, nucleo pattern parser handles them itself
+fn split(query: &str) -> Vec<&str> {
+    let mut terms = Vec::new();
+    let mut start = None;
+    let mut escaped = false;
+    for (i, c) in query.char_indices() {
+        match (c.is_whitespace() && !escaped, start) {
+            (true, Some(s)) => {
+                terms.push(&query[s..i]);
+                start = None;
+            }
+            (true, None) => (),
+            (false, None) => start = Some(i),
+            (false, Some(_)) => (),
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    if let Some(s) = start {
+        terms.push(&query[s..]);
+    }
+    terms
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn session() -> Session {
+        toml::from_str(
+            r#"
+            name = "app"
+            root = "/src/my app"
+            tags = ["Work", "rust"]
+            socket_name = "work"
+            window = [{ name = "editor" }, { name = "shell" }]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split("  a  b\tc "), ["a", "b", "c"]);
+        assert_eq!(split("my\\ app root:a\\ b"), ["my\\ app", "root:a\\ b"]);
+        assert_eq!(split("a\\\\ b"), ["a\\\\", "b"]);
+        assert!(split("   ").is_empty());
+    }
+
+    #[test]
+    fn test_text() {
+        let query = Query::parse("^ap  !test unknown:field tag:work p$");
+        assert_eq!(query.text(), "^ap !test unknown:field p$");
+        assert_eq!(query.filters.len(), 1);
+        assert_eq!(Query::parse("my\\ app").text(), "my\\ app");
+        // empty value is not a filter
+        assert_eq!(Query::parse("tag:").text(), "tag:");
+    }
+
+    #[test]
+    fn test_filters() {
+        let s = session();
+        let passes = |query: &str, state| Query::parse(query).filter(&s, &state);
+
+        assert!(passes("state:cr", State::Created));
+        assert!(!passes("state:cr", State::None));
+        assert!(passes("!state:attached", State::Created));
+
+        assert!(passes("root:/SRC/my\\ app", State::None));
+        assert!(!passes("root:/src/other", State::None));
+
+        assert!(passes("tag:work", State::None));
+        assert!(!passes("tag:wor", State::None));
+        assert!(!passes("!tag:rust", State::None));
+
+        assert!(passes("server:wo", State::None));
+        assert!(!passes("server:default", State::None));
+
+        assert!(passes("window:edit", State::None));
+        assert!(!passes("window:vim", State::None));
+
+        // all filters must pass
+        assert!(passes("tag:rust window:shell state:none", State::None));
+        assert!(!passes("tag:rust window:vim", State::None));
+    }
+
+    #[test]
+    fn test_window() {
+        let s = session();
+        assert_eq!(Query::parse("app window:SH").window(&s), Some("shell"));
+        assert_eq!(Query::parse("window:e").window(&s), Some("editor"));
+        assert_eq!(Query::parse("!window:editor").window(&s), None);
+        assert_eq!(Query::parse("app").window(&s), None);
+    }
+}
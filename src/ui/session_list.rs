@@ -1,14 +1,21 @@
-use crossterm::event::{Event, KeyCode, KeyModifiers};
-use indexmap::IndexMap;
-use nucleo::Utf32String;
-use ratatui::{
-    style::Stylize,
-    text::{Span, ToSpan},
-    widgets::Widget,
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseEventKind};
+use nucleo::{
+    Nucleo, Utf32String,
+    pattern::{CaseMatching, Normalization},
+};
+use ratatui::{layout::Rect, style::Stylize, text::Span, widgets::Widget};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Range,
+    path::PathBuf,
+    sync::{Arc, Mutex},
 };
-use std::{collections::HashSet, path::PathBuf};
 
-use crate::{config, ui::ParagraphBuilder};
+use crate::{
+    config::{self, SearchField},
+    ui::{ParagraphBuilder, Query, Theme, paragraph},
+};
 
 /// Session state
 #[derive(Debug, Clone, PartialEq)]
@@ -22,45 +29,106 @@ pub enum State {
 #[derive(Debug, Clone)]
 pub struct Session {
     pub state: State,
+    pub server: config::Server,
     pub root: PathBuf,
+    pub env: BTreeMap<String, String>,
+    pub options: BTreeMap<String, String>,
+    pub tmux_commands: Vec<String>,
+    pub focus: Option<config::Focus>,
+    /// Whether to open session through grouped view session
+    pub view: bool,
+    /// Whether to detach other clients of session
+    pub detach: bool,
+    /// Whether to attach session read-only
+    pub read_only: bool,
     pub windows: Vec<config::Window>,
 }
 
+/// Session is identified by its server and name, the same name may be used on several servers
+pub type SessionKey = (config::Server, String);
+
+/// Separates fields in haystack of matcher item
+const SEPARATOR: &str = "\n";
+
 /// String whith matched indices
+/// If match was found only in fields other than name, the best of them is stored too
 #[derive(Debug, Clone)]
 pub struct MatchedString {
     s: Utf32String,
     indices: HashSet<u32>,
+    detail: Option<(SearchField, Utf32String, HashSet<u32>)>,
+}
+
+/// Matcher item, all searchable fields of session are joined into one haystack,
+/// so that every term of prompt (including negations) applies to session as a whole
+struct Entry {
+    session: config::Session,
+    /// Character ranges of fields in haystack
+    fields: Vec<(SearchField, Range<u32>)>,
+}
+
+/// Matched session, highlighted characters are found only when it is rendered
+struct Match {
+    entry: Arc<Entry>,
+    state: State,
+    /// Number of grouped view sessions
+    views: usize,
+}
+
+/// Handle for adding sessions to list from any thread
+#[derive(Clone)]
+pub struct SessionInjector {
+    inner: nucleo::Injector<Arc<Entry>>,
+    fields: Vec<SearchField>,
+    names: Arc<Mutex<HashSet<SessionKey>>>,
+    roots: Arc<Mutex<HashSet<PathBuf>>>,
 }
 
 /// Selectable list
+/// Matching runs in background, so new sessions and prompt are applied on next `tick`
 pub struct SessionList {
-    items: IndexMap<Utf32String, Session>,
-    matches: Vec<MatchedString>,
-    prompt: Utf32String,
+    nucleo: Nucleo<Arc<Entry>>,
+    fields: Vec<SearchField>,
+    names: Arc<Mutex<HashSet<SessionKey>>>,
+    roots: Arc<Mutex<HashSet<PathBuf>>>,
+    states: HashMap<config::Server, HashMap<String, State>>,
+    views: HashMap<config::Server, HashMap<String, usize>>,
+    matches: Vec<Match>,
+    marked: HashSet<SessionKey>,
+    /// Sessions removed from list, matcher items can not be removed, so they are skipped
+    removed: HashSet<SessionKey>,
+    query: Query,
+    matcher: RefCell<nucleo::Matcher>,
     selected: usize,
+    /// Whether the best match is at the bottom
+    rev: bool,
+    dirty: bool,
 }
 
 pub struct SessionListWidget<'a> {
     inner: &'a SessionList,
+    theme: &'a Theme,
 }
 
 impl Session {
     pub fn new(config: &config::Session, state: State) -> Self {
         Self {
+            view: config.view && state == State::Attached,
+            detach: false,
+            read_only: false,
             state,
+            server: config.server.clone(),
             root: config.root.clone(),
+            env: config.env.clone(),
+            options: config.options.clone(),
+            tmux_commands: config.tmux_commands.clone(),
+            focus: config.focus.clone(),
             windows: config.window.clone(),
         }
     }
 }
 
 impl MatchedString {
-    /// Returns raw string
-    pub fn to_string(&self) -> String {
-        self.s.to_string()
-    }
-
     /// Returns iterator over characters and indicator whether each of them was matched or not
     pub fn chars(&self) -> impl Iterator<Item = (char, bool)> {
         self.s
@@ -69,130 +137,451 @@ impl MatchedString {
             .enumerate()
             .map(|(i, c)| (c, self.indices.contains(&(i as u32))))
     }
+
+    /// Returns field, which was matched, if it is not name, and its characters with indicators
+    pub fn detail(&self) -> Option<(SearchField, impl Iterator<Item = (char, bool)>)> {
+        let (field, text, indices) = self.detail.as_ref()?;
+        Some((
+            *field,
+            text.slice(..)
+                .chars()
+                .enumerate()
+                .map(|(i, c)| (c, indices.contains(&(i as u32)))),
+        ))
+    }
 }
 
-impl Into<String> for MatchedString {
-    fn into(self) -> String {
-        self.s.to_string()
+impl std::fmt::Display for MatchedString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.s.fmt(f)
+    }
+}
+
+impl From<MatchedString> for String {
+    fn from(value: MatchedString) -> Self {
+        value.s.to_string()
+    }
+}
+
+impl Entry {
+    fn new(session: config::Session, fields: &[SearchField]) -> Self {
+        let mut start = 0;
+        let fields = fields
+            .iter()
+            .map(|field| {
+                let len = Utf32String::from(text(&session, *field)).len() as u32;
+                let range = start..start + len;
+                start = range.end + SEPARATOR.len() as u32;
+                (*field, range)
+            })
+            .collect();
+        Self { session, fields }
+    }
+
+    fn haystack(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(field, _)| text(&self.session, *field))
+            .collect::<Vec<_>>()
+            .join(SEPARATOR)
+    }
+
+    /// Splits indices matched in haystack by fields, name is highlighted if it was matched,
+    /// otherwise field with most matched characters is shown
+    fn matched(&self, indices: &[u32]) -> MatchedString {
+        let mut name = HashSet::new();
+        let mut detail: Option<(SearchField, HashSet<u32>)> = None;
+        for (field, range) in self.fields.iter() {
+            let matched: HashSet<u32> = indices
+                .iter()
+                .filter(|i| range.contains(i))
+                .map(|i| i - range.start)
+                .collect();
+            match field {
+                SearchField::Name => name = matched,
+                _ if matched.len() > detail.as_ref().map_or(0, |(_, d)| d.len()) => {
+                    detail = Some((*field, matched))
+                }
+                _ => (),
+            }
+        }
+
+        MatchedString {
+            s: self.session.name.as_str().into(),
+            detail: detail
+                .filter(|_| name.is_empty())
+                .map(|(field, d)| (field, text(&self.session, field).into(), d)),
+            indices: name,
+        }
+    }
+}
+
+fn key(session: &config::Session) -> SessionKey {
+    (session.server.clone(), session.name.clone())
+}
+
+/// Returns text of session field, which is matched against prompt
+fn text(session: &config::Session, field: SearchField) -> String {
+    match field {
+        SearchField::Name => session.name.clone(),
+        SearchField::Root => session.root.to_string_lossy().to_string(),
+        SearchField::Windows => session
+            .window
+            .iter()
+            .flat_map(|w| [w.name.as_str(), w.command.as_str()])
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+        SearchField::Tags => session.tags.join(" "),
+        SearchField::Description => session.description.clone(),
+    }
+}
+
+impl SessionInjector {
+    /// Inserts new session, order preserved, if session with the same name already exists
+    /// on the same server, no insertion occurs
+    /// Returns whether session was inserted
+    pub fn push(&self, session: config::Session) -> bool {
+        if !self.names.lock().unwrap().insert(key(&session)) {
+            return false;
+        }
+        self.roots.lock().unwrap().insert(session.root.clone());
+        let entry = Arc::new(Entry::new(session, &self.fields));
+        self.inner
+            .push(entry, |e, columns| columns[0] = e.haystack().into());
+        true
+    }
+
+    /// Whether there is session with given root already
+    pub fn has_root(&self, root: &std::path::Path) -> bool {
+        self.roots.lock().unwrap().contains(root)
     }
 }
 
 impl SessionList {
-    /// Creates empty list
-    pub fn new() -> Self {
+    /// Creates empty list, prompt is matched against given session fields
+    /// `notify` is called from background thread, when matching results are ready
+    pub fn new(fields: &[SearchField], notify: Arc<dyn Fn() + Sync + Send>) -> Self {
         Self {
-            items: IndexMap::new(),
+            nucleo: Nucleo::new(nucleo::Config::DEFAULT, notify, None, 1),
+            fields: fields.to_vec(),
+            names: Arc::new(Mutex::new(HashSet::new())),
+            roots: Arc::new(Mutex::new(HashSet::new())),
+            states: HashMap::new(),
+            views: HashMap::new(),
             matches: Vec::new(),
-            prompt: Utf32String::Ascii(String::new().into_boxed_str()),
+            marked: HashSet::new(),
+            removed: HashSet::new(),
+            query: Query::parse(""),
+            matcher: RefCell::new(nucleo::Matcher::new(nucleo::Config::DEFAULT)),
             selected: 0,
+            rev: true,
+            dirty: false,
         }
     }
 
-    /// Inserts new item, order preserved, if key already exists, no insertion occurs
-    pub fn insert(&mut self, session: &config::Session, state: State) {
-        let s: Utf32String = session.name.as_str().into();
-        if !self.items.contains_key(&s) {
-            self.items.insert(s, Session::new(session, state));
+    /// Sets whether the best match is drawn at the bottom or at the top
+    pub fn set_rev(&mut self, rev: bool) {
+        self.rev = rev;
+    }
+
+    /// Returns handle for inserting sessions
+    pub fn injector(&self) -> SessionInjector {
+        SessionInjector {
+            inner: self.nucleo.injector(),
+            fields: self.fields.clone(),
+            names: self.names.clone(),
+            roots: self.roots.clone(),
         }
-        self.update();
     }
 
     /// Returns number of items
     pub fn len(&self) -> usize {
-        self.items.len()
+        self.nucleo.snapshot().item_count() as usize - self.removed.len()
+    }
+
+    /// Returns number of matched items
+    pub fn matched_len(&self) -> usize {
+        self.matches.len()
     }
 
     /// Updates prompt
     pub fn prompt<S: AsRef<str>>(&mut self, prompt: S) {
-        self.prompt = prompt.as_ref().into();
-        self.update();
+        let query = Query::parse(prompt.as_ref());
+        let (old, new) = (self.query.text(), query.text());
+        if old != new {
+            let append = new.starts_with(old) && !old.ends_with('\\');
+            self.nucleo
+                .pattern
+                .reparse(0, new, CaseMatching::Smart, Normalization::Smart, append);
+        }
+        self.query = query;
+        self.dirty = true;
     }
 
-    /// Returns iterator over all matched items
-    pub fn matched_items(
-        &self,
-    ) -> impl Iterator<Item = (&MatchedString, &Session)> + ExactSizeIterator {
-        self.matches
-            .iter()
-            .map(|s| (s, self.items.get(&s.s).unwrap()))
+    /// Updates states of sessions and numbers of their views by server,
+    /// sessions not present in map are considered not created
+    pub fn set_states(
+        &mut self,
+        states: HashMap<config::Server, HashMap<String, State>>,
+        views: HashMap<config::Server, HashMap<String, usize>>,
+    ) {
+        if self.states != states || self.views != views {
+            self.states = states;
+            self.views = views;
+            self.dirty = true;
+        }
+    }
+
+    /// Collects matching results, returns whether list has changed
+    pub fn tick(&mut self) -> bool {
+        let status = self.nucleo.tick(10);
+        if status.changed || self.dirty {
+            self.update();
+            return true;
+        }
+        false
     }
 
     /// Returns currently selected item
-    pub fn selected(&self) -> Option<(&MatchedString, &Session)> {
-        self.matches
-            .get(self.selected)
-            .map(|s| (s, self.items.get(&s.s).unwrap()))
+    /// With `window:` filter in prompt, session is focused on matching window
+    pub fn selected(&self) -> Option<(MatchedString, Session)> {
+        let m = self.matches.get(self.selected)?;
+        let mut session = Session::new(&m.entry.session, m.state.clone());
+        if let Some(window) = self.query.window(&m.entry.session) {
+            session.focus = Some(config::Focus {
+                window: window.to_string(),
+                pane: None,
+                always: true,
+            });
+        }
+        Some((self.matched(&m.entry), session))
+    }
+
+    /// Returns number of marked items
+    pub fn marked_len(&self) -> usize {
+        self.marked.len()
+    }
+
+    /// Returns all marked items, including ones not matching current prompt
+    pub fn marked_items(&self) -> Vec<(String, Session)> {
+        let snapshot = self.nucleo.snapshot();
+        (0..snapshot.item_count())
+            .filter_map(|i| snapshot.get_item(i))
+            .map(|item| &item.data.session)
+            .filter(|s| self.marked.contains(&key(s)))
+            .map(|s| (s.name.clone(), Session::new(s, self.state(s))))
+            .collect()
+    }
+
+    /// Unmark item by server and name
+    pub fn unmark(&mut self, server: &config::Server, name: &str) {
+        self.marked.remove(&(server.clone(), name.to_string()));
+    }
+
+    /// Removes session from list, it may be listed again with `restore`
+    pub fn remove(&mut self, server: &config::Server, name: &str) {
+        let key = (server.clone(), name.to_string());
+        self.marked.remove(&key);
+        self.removed.insert(key);
+        self.dirty = true;
     }
 
-    /// Get selected index
-    pub fn get_selected_index(&mut self) -> usize {
-        self.selected
+    /// Lists removed session again, returns whether it was removed
+    pub fn restore(&mut self, server: &config::Server, name: &str) -> bool {
+        let restored = self.removed.remove(&(server.clone(), name.to_string()));
+        self.dirty |= restored;
+        restored
     }
 
-    /// Set selected index
-    pub fn set_selected(&mut self, selected: usize) {
-        self.selected = selected;
-        if self.selected >= self.matches.len() && self.matches.len() > 0 {
-            self.selected = self.matches.len() - 1;
+    /// Toggles mark on selected item
+    pub fn toggle_mark(&mut self) {
+        if let Some(m) = self.matches.get(self.selected)
+            && !self.marked.remove(&key(&m.entry.session))
+        {
+            self.marked.insert(key(&m.entry.session));
         }
     }
 
-    fn update(&mut self) {
-        let mut res = Vec::new();
+    /// Marks all matched items, or unmarks them if all of them are already marked
+    pub fn toggle_mark_all(&mut self) {
+        if self
+            .matches
+            .iter()
+            .all(|m| self.marked.contains(&key(&m.entry.session)))
+        {
+            for m in self.matches.iter() {
+                self.marked.remove(&key(&m.entry.session));
+            }
+        } else {
+            self.marked
+                .extend(self.matches.iter().map(|m| key(&m.entry.session)));
+        }
+    }
 
-        let mut matcher = nucleo::Matcher::new(nucleo::Config::DEFAULT);
-        for item in self.items.keys() {
-            let mut indices = Vec::new();
-            let score = matcher.fuzzy_indices(item.slice(..), self.prompt.slice(..), &mut indices);
+    fn state(&self, session: &config::Session) -> State {
+        self.states
+            .get(&session.server)
+            .and_then(|s| s.get(&session.name))
+            .cloned()
+            .unwrap_or(State::None)
+    }
+
+    fn update(&mut self) {
+        self.dirty = false;
+        let snapshot = self.nucleo.snapshot();
 
-            if score.is_some() {
-                res.push((item.clone(), score.unwrap(), indices));
+        let mut matches = Vec::new();
+        for item in snapshot.matched_items(..) {
+            let session = &item.data.session;
+            let state = self.state(session);
+            if !self.query.filter(session, &state) || self.removed.contains(&key(session)) {
+                continue;
             }
+
+            matches.push(Match {
+                entry: item.data.clone(),
+                state,
+                views: self
+                    .views
+                    .get(&session.server)
+                    .and_then(|v| v.get(&session.name))
+                    .copied()
+                    .unwrap_or_default(),
+            });
         }
 
-        res.sort_by_key(|(_, score, _)| u16::MAX - score);
-        self.matches = res
-            .into_iter()
-            .map(|(s, _, indices)| MatchedString {
-                s: s,
-                indices: HashSet::from_iter(indices.into_iter()),
-            })
-            .collect();
+        // without prompt show attached sessions first, then created ones
+        if self.query.text().is_empty() {
+            matches.sort_by_key(|m| match m.state {
+                State::Attached => 0,
+                State::Created => 1,
+                State::None => 2,
+            });
+        }
+        self.matches = matches;
+
+        if self.selected >= self.matches.len() {
+            self.selected = self.matches.len().saturating_sub(1);
+        }
+    }
 
-        self.set_selected(self.selected);
+    /// Matches prompt against item again to find highlighted characters
+    fn matched(&self, entry: &Entry) -> MatchedString {
+        let pattern = self.nucleo.snapshot().pattern().column_pattern(0);
+        let haystack = Utf32String::from(entry.haystack());
+        let mut indices = Vec::new();
+        pattern.indices(
+            haystack.slice(..),
+            &mut self.matcher.borrow_mut(),
+            &mut indices,
+        );
+        entry.matched(&indices)
     }
 
     pub fn handle_event(&mut self, evt: &Event) -> bool {
         match evt {
-            Event::Key(event) => {
-                if event.is_press() || event.is_repeat() {
-                    match (event.code, event.modifiers) {
-                        (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
-                            if self.selected + 1 < self.matches.len() {
-                                self.selected += 1;
-                            }
-                            true
-                        }
-                        (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
-                            if self.selected > 0 {
-                                self.selected -= 1;
-                            }
-                            true
-                        }
-                        _ => false,
+            Event::Key(event) if event.is_press() || event.is_repeat() => {
+                match (event.code, event.modifiers) {
+                    (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                        self.move_up();
+                        true
                     }
-                } else {
-                    false
+                    (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                        self.move_down();
+                        true
+                    }
+                    (KeyCode::Tab, _) => {
+                        self.toggle_mark();
+                        self.select_next();
+                        true
+                    }
+                    (KeyCode::BackTab, _) => {
+                        self.toggle_mark();
+                        self.select_prev();
+                        true
+                    }
+                    (KeyCode::Char('a'), KeyModifiers::ALT) => {
+                        self.toggle_mark_all();
+                        true
+                    }
+                    _ => false,
                 }
             }
+            Event::Mouse(event) => match event.kind {
+                MouseEventKind::ScrollUp => {
+                    self.move_up();
+                    true
+                }
+                MouseEventKind::ScrollDown => {
+                    self.move_down();
+                    true
+                }
+                _ => false,
+            },
             _ => false,
         }
     }
+
+    /// Selects match rendered at row `y` of list area, returns its session if there is one
+    /// Selection scrolls list, so the same row may show other session afterwards
+    pub fn click(&mut self, area: Rect, y: u16) -> Option<SessionKey> {
+        let (skip, scroll) = self.visible(area.height);
+        let len = usize::min(
+            self.matches.len().saturating_sub(skip),
+            area.height as usize,
+        );
+        match paragraph::line_at(len, scroll, self.rev, area, y) {
+            Some(i) => {
+                self.selected = skip + i;
+                Some(key(&self.matches[self.selected].entry.session))
+            }
+            None => None,
+        }
+    }
+
+    /// Returns number of matches skipped before visible ones and scroll of rendered paragraph,
+    /// selection is kept a few lines away from the edge
+    fn visible(&self, height: u16) -> (usize, usize) {
+        let scroll = self.selected + 3;
+        let skip = scroll.saturating_sub(height as usize);
+        (skip, scroll - skip)
+    }
+
+    /// Moves selection one row up on screen
+    fn move_up(&mut self) {
+        match self.rev {
+            true => self.select_next(),
+            false => self.select_prev(),
+        }
+    }
+
+    /// Moves selection one row down on screen
+    fn move_down(&mut self) {
+        match self.rev {
+            true => self.select_prev(),
+            false => self.select_next(),
+        }
+    }
+
+    /// Selects next worse match
+    fn select_next(&mut self) {
+        if self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Selects next better match
+    fn select_prev(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        }
+    }
 }
 
 impl<'a> SessionListWidget<'a> {
-    pub fn new(list: &'a SessionList) -> Self {
-        Self { inner: list }
+    pub fn new(list: &'a SessionList, theme: &'a Theme) -> Self {
+        Self { inner: list, theme }
     }
 }
 
@@ -200,18 +589,34 @@ impl<'a> Widget for SessionListWidget<'a> {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
         let mut b = ParagraphBuilder::new();
 
-        for (i, line) in self.inner.matches.iter().enumerate() {
+        // only visible lines are built, list may be huge
+        let (skip, scroll) = self.inner.visible(area.height);
+        for (i, m) in self
+            .inner
+            .matches
+            .iter()
+            .enumerate()
+            .skip(skip)
+            .take(area.height as usize)
+        {
+            let (line, theme) = (self.inner.matched(&m.entry), self.theme);
             if i == self.inner.selected {
-                b.p("▌".to_span().magenta());
+                b.p(theme.selected.as_str().magenta());
             } else {
-                b.p("▎".to_span().dark_gray());
+                b.p(theme.unselected.as_str().dark_gray());
             }
-            match self.inner.items[&line.s].state {
-                State::None => b.p("  ".to_span()),
-                State::Created => b.p("◇ ".to_span().blue()),
-                State::Attached => b.p("◆ ".to_span().blue()),
+            // state and mark columns are as wide as their glyphs
+            let state_width = width(&theme.created).max(width(&theme.attached));
+            match m.state {
+                State::None => b.p(pad("", state_width).into()),
+                State::Created => b.p(pad(&theme.created, state_width).blue()),
+                State::Attached => b.p(pad(&theme.attached, state_width).blue()),
+            }
+            if self.inner.marked.contains(&key(&m.entry.session)) {
+                b.p(pad(&theme.marked, width(&theme.marked)).magenta().bold());
+            } else {
+                b.p(pad("", width(&theme.marked)).into());
             }
-            b.p("  ".to_span());
             for (c, matched) in line.chars() {
                 if matched {
                     b.p(Span::from(c.to_string()).green());
@@ -219,15 +624,171 @@ impl<'a> Widget for SessionListWidget<'a> {
                     b.p(Span::from(c.to_string()));
                 }
             }
+            if m.views > 0 {
+                b.p(format!(" +{}", m.views).blue());
+            }
+            if !m.entry.session.server.is_default() {
+                b.p(format!(" [{}]", m.entry.session.server).dark_gray());
+            }
+            if let Some((field, chars)) = line.detail() {
+                b.p(format!("  {}: ", field).dark_gray());
+                for (c, matched) in chars {
+                    if matched {
+                        b.p(Span::from(c.to_string()).green());
+                    } else {
+                        b.p(Span::from(c.to_string()).dark_gray());
+                    }
+                }
+            }
             b.br();
         }
 
-        b.line_mut(self.inner.selected)
-            .map(|line| *line = line.clone().bold().italic());
+        if let Some(line) = (self.inner.selected.checked_sub(skip)).and_then(|i| b.line_mut(i)) {
+            *line = line.clone().bold().italic();
+        }
+
+        let b = b.scroll(scroll);
+        match self.inner.rev {
+            true => b.rev().finish().render(area, buf),
+            false => b.finish().render(area, buf),
+        }
+    }
+}
+
+fn width(s: &str) -> usize {
+    Span::raw(s).width()
+}
+
+/// Pads glyph with spaces to given width and separates it from the rest of row
+fn pad(glyph: &str, width: usize) -> String {
+    format!(
+        "{}{}",
+        glyph,
+        " ".repeat(width.saturating_sub(self::width(glyph)) + 1)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn session(name: &str, root: &str) -> config::Session {
+        toml::from_str(&format!(
+            "name = '{name}'\nroot = '{root}'\nwindow = [{{ name = 'shell' }}]"
+        ))
+        .unwrap()
+    }
+
+    fn list(sessions: &[(&str, &str)]) -> SessionList {
+        let list = SessionList::new(&[SearchField::Name, SearchField::Root], Arc::new(|| {}));
+        let injector = list.injector();
+        for (name, root) in sessions {
+            injector.push(session(name, root));
+        }
+        list
+    }
+
+    fn matches(list: &mut SessionList, prompt: &str) -> Vec<String> {
+        list.prompt(prompt);
+        while list.nucleo.tick(100).running {}
+        list.update();
+        list.matches
+            .iter()
+            .map(|m| m.entry.session.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_fields() {
+        let mut list = list(&[("app", "/src/app"), ("web", "/src/legacy/web")]);
+        // negation excludes session if any of its fields matches
+        assert_eq!(matches(&mut list, "src !legacy"), ["app"]);
+        assert_eq!(list.len(), 2);
+        assert_eq!(matches(&mut list, "!web"), ["app"]);
+        // terms may match different fields
+        assert_eq!(matches(&mut list, "'web 'legacy"), ["web"]);
+        assert!(matches(&mut list, "'app 'legacy").is_empty());
+
+        // name is highlighted, other field is shown only if name is not matched
+        matches(&mut list, "app");
+        assert!(list.matched(&list.matches[0].entry).detail().is_none());
+        matches(&mut list, "legacy");
+        let matched = list.matched(&list.matches[0].entry);
+        let (field, chars) = matched.detail().unwrap();
+        let matched: String = chars.filter(|(_, m)| *m).map(|(c, _)| c).collect();
+        assert_eq!((field, matched.as_str()), (SearchField::Root, "legacy"));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut list = list(&[("app", "/src/app"), ("web", "/src/web")]);
+        let server = config::Server::default();
+        assert_eq!(matches(&mut list, ""), ["app", "web"]);
+        list.toggle_mark_all();
+
+        list.remove(&server, "app");
+        assert_eq!(matches(&mut list, ""), ["web"]);
+        assert_eq!((list.len(), list.marked_len()), (1, 1));
+
+        assert!(list.restore(&server, "app"));
+        assert!(!list.restore(&server, "app"));
+        assert_eq!(matches(&mut list, ""), ["app", "web"]);
+        assert_eq!(list.len(), 2);
+    }
+
+    #[test]
+    fn test_click() {
+        let mut list = list(&["s0", "s1", "s2", "s3", "s4", "s5"].map(|n| (n, "/src")));
+        matches(&mut list, "");
+        let area = Rect::new(0, 1, 20, 3);
+        let clicked = |list: &mut SessionList, y| list.click(area, y).map(|(_, name)| name);
+
+        // the best match is at the bottom
+        assert_eq!(clicked(&mut list, 3).as_deref(), Some("s0"));
+        assert_eq!(clicked(&mut list, 1).as_deref(), Some("s2"));
+        // list is scrolled to keep selection away from the edge
+        assert_eq!(clicked(&mut list, 1).as_deref(), Some("s4"));
+        assert_eq!(list.selected().unwrap().0.to_string(), "s4");
+        assert_eq!(clicked(&mut list, 0), None);
+        assert_eq!(clicked(&mut list, 4), None);
+    }
+
+    #[test]
+    fn test_pad() {
+        assert_eq!(pad("", 1), "  ");
+        assert_eq!(pad("◆", 1), "◆ ");
+        // glyphs of different widths are padded to the same column
+        for glyph in ["", "o", "◇", "🍰"] {
+            assert_eq!(width(&pad(glyph, 2)), 3, "{:?}", glyph);
+        }
+        // too wide glyph is still separated from the rest of row
+        assert_eq!(pad("🍰", 1), "🍰 ");
+    }
+
+    #[test]
+    fn test_servers() {
+        let list = list(&[("app", "/src/app")]);
+        let injector = list.injector();
+        let mut work = session("app", "/work/app");
+        work.server.socket_name = Some("work".into());
+        assert!(injector.push(work.clone()));
+        assert!(!injector.push(work.clone()));
+
+        // the same name on other server is distinct session
+        let mut list = list;
+        assert_eq!(matches(&mut list, "app"), ["app", "app"]);
+        list.toggle_mark();
+        let marked = list.marked_items();
+        assert_eq!(marked.len(), 1);
 
-        b.scroll(self.inner.selected + 3)
-            .rev()
-            .finish()
-            .render(area, buf);
+        let server = marked[0].1.server.clone();
+        let other = match server.is_default() {
+            true => work.server.clone(),
+            false => config::Server::default(),
+        };
+        list.unmark(&other, "app");
+        assert_eq!(list.marked_len(), 1);
+        list.unmark(&server, "app");
+        assert_eq!(list.marked_len(), 0);
     }
 }
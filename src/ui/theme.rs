@@ -0,0 +1,91 @@
+use ratatui::symbols::border;
+
+use crate::config;
+
+/// Border of ASCII-only theme
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// Glyphs and borders picker is drawn with, resolved from `[ui]` config
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub selected: String,
+    pub unselected: String,
+    pub created: String,
+    pub attached: String,
+    pub marked: String,
+    /// Line drawn next to prompt
+    pub rule: &'static str,
+    pub border: border::Set<'static>,
+}
+
+impl Theme {
+    pub fn new(config: &config::Ui) -> Self {
+        let glyph = |glyph: &Option<String>, unicode: &str, ascii: &str| match glyph {
+            Some(glyph) => glyph.clone(),
+            None if config.ascii => ascii.to_string(),
+            None => unicode.to_string(),
+        };
+        let g = &config.glyphs;
+        Self {
+            selected: glyph(&g.selected, "▌", ">"),
+            unselected: glyph(&g.unselected, "▎", " "),
+            created: glyph(&g.created, "◇", "o"),
+            attached: glyph(&g.attached, "◆", "*"),
+            marked: glyph(&g.marked, "+", "+"),
+            rule: if config.ascii { "-" } else { "─" },
+            border: if config.ascii {
+                ASCII_BORDER
+            } else {
+                border::ROUNDED
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn theme(toml: &str) -> Theme {
+        Theme::new(&toml::from_str(toml).unwrap())
+    }
+
+    #[test]
+    fn test_glyphs() {
+        let unicode = theme("");
+        assert_eq!((unicode.selected.as_str(), unicode.rule), ("▌", "─"));
+        assert_eq!(unicode.border, border::ROUNDED);
+
+        let ascii = theme("ascii = true");
+        assert_eq!((ascii.selected.as_str(), ascii.rule), (">", "-"));
+        assert_eq!(ascii.border, ASCII_BORDER);
+
+        // explicit glyphs are kept in ASCII theme
+        let custom = theme("ascii = true\nglyphs = { selected = '→', attached = '@' }");
+        assert_eq!(
+            [custom.selected, custom.attached, custom.created],
+            ["→", "@", "o"]
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let ui: config::Ui = toml::from_str("orientation = 'top-down'\nprompt = 'top'").unwrap();
+        assert_eq!(ui.orientation, config::Orientation::TopDown);
+        assert_eq!(ui.prompt, config::PromptPosition::Top);
+        let ui: config::Ui = toml::from_str("").unwrap();
+        assert_eq!(ui.orientation, config::Orientation::BottomUp);
+        assert_eq!(ui.prompt, config::PromptPosition::Bottom);
+        assert!(toml::from_str::<config::Ui>("orientation = 'top_down'").is_err());
+        assert!(toml::from_str::<config::Ui>("prompt = 'Top'").is_err());
+    }
+}